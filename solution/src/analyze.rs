@@ -0,0 +1,108 @@
+use std::{io::Write, path::Path};
+
+use anyhow::Context;
+
+use crate::{
+    du::DirectorySizes,
+    format::{FormatSize, SIZE_WIDTH},
+    histogram::SizeBar,
+    is_hidden_entry, stats,
+    theme::Theme,
+};
+
+/// Something directly within the directory being analyzed
+struct Child {
+    name: String,
+    /// The total size of its contents for directories
+    size: u64,
+    is_dir: bool,
+}
+
+/// How much of a directory's size each entry directly within it takes up,
+/// printed by `analyze`
+pub struct Analysis {
+    /// The largest first
+    children: Vec<Child>,
+    total: u64,
+}
+
+impl Analysis {
+    /// Sums up the sizes of everything directly within `root`, including the
+    /// hidden entries only if `hidden`. With `deduplicate_hardlinks`, files
+    /// with several hard links are only counted once
+    pub fn compute(root: &Path, hidden: bool, deduplicate_hardlinks: bool) -> anyhow::Result<Self> {
+        let sizes = DirectorySizes::compute(root, root, None, deduplicate_hardlinks)?;
+        let total = sizes
+            .get(root)
+            .context(format!("{} isn't a directory", root.display()))?;
+
+        let mut children = Vec::new();
+        for entry in
+            std::fs::read_dir(root).context(format!("Failed reading {}", root.display()))?
+        {
+            let entry = entry.context(format!("Failed reading {}", root.display()))?;
+            let name = entry.file_name();
+            if !hidden && is_hidden_entry(&name, || entry.metadata().ok()) {
+                continue;
+            }
+
+            let path = entry.path();
+            let metadata = stats::stat_call(|| entry.metadata())
+                .context(format!("Failed reading {}", path.display()))?;
+            let size = match sizes.get(&path) {
+                Some(size) if metadata.is_dir() => size,
+                _ => metadata.len(),
+            };
+            children.push(Child {
+                name: name.to_string_lossy().into_owned(),
+                size,
+                is_dir: metadata.is_dir(),
+            });
+        }
+        children.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.name.cmp(&b.name)));
+
+        Ok(Self { children, total })
+    }
+
+    /// Writes the size, a bar and the share of the total of at most `limit`
+    /// children, followed by the total
+    pub fn write(
+        &self,
+        mut output: impl Write,
+        limit: Option<usize>,
+        theme: &Theme,
+    ) -> std::io::Result<()> {
+        for child in self.children.iter().take(limit.unwrap_or(usize::MAX)) {
+            let size = FormatSize {
+                size: child.size,
+                directory: None,
+                width: SIZE_WIDTH,
+                theme,
+            };
+            let bar = SizeBar {
+                size: child.size,
+                largest: self.total,
+                theme,
+            };
+            let percentage = if self.total == 0 {
+                0.0
+            } else {
+                child.size as f64 * 100.0 / self.total as f64
+            };
+            let style = if child.is_dir { theme.dir } else { theme.file };
+            writeln!(
+                output,
+                "{size}\t{bar}\t{percentage:>5.1}%\t{}",
+                style.paint(&child.name)
+            )?;
+        }
+
+        let total = FormatSize {
+            size: self.total,
+            directory: None,
+            width: SIZE_WIDTH,
+            theme,
+        };
+        writeln!(output, "{total}\t{}", theme.header.paint("Total"))
+    }
+}
@@ -0,0 +1,25 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+
+/// When the system was booted, from how long it has been up according to
+/// `/proc/uptime`
+#[cfg(target_os = "linux")]
+pub fn boot_time() -> anyhow::Result<SystemTime> {
+    let uptime = std::fs::read_to_string("/proc/uptime").context("Failed reading /proc/uptime")?;
+    // The first number is the uptime in seconds, the second how long cores were idle
+    let seconds: f64 = uptime
+        .split_whitespace()
+        .next()
+        .and_then(|seconds| seconds.parse().ok())
+        .context(format!("Unexpected contents of /proc/uptime: {uptime}"))?;
+
+    SystemTime::now()
+        .checked_sub(Duration::from_secs_f64(seconds))
+        .context("The system claims to have been up since before 1970")
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn boot_time() -> anyhow::Result<SystemTime> {
+    anyhow::bail!("Finding out when the system was booted is only supported on Linux")
+}
@@ -0,0 +1,104 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, BufWriter, ErrorKind},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+/// What a directory directly contains, enough to sum its size without listing
+/// it or stat-ing its files again
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct DirectoryContents {
+    /// The total size of the files (and symlinks) with a single link
+    pub files_size: u64,
+    /// The directories and the files with several hard links within, in the
+    /// order they're listed so hard links are attributed like without a cache
+    pub children: Vec<Child>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum Child {
+    Directory(PathBuf),
+    /// A file with several hard links, kept apart so `--deduplicate-hardlinks`
+    /// can still count it once
+    HardLink {
+        device: u64,
+        inode: u64,
+        size: u64,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedDirectory {
+    modified: SystemTime,
+    contents: DirectoryContents,
+}
+
+/// Directory contents from a previous run, keyed by absolute path, reused for
+/// directories whose modification time hasn't changed since. Adding, removing
+/// or renaming an entry changes the modification time of its directory, but
+/// changing a file in place doesn't
+#[derive(Default)]
+pub struct SizeCache {
+    previous: HashMap<PathBuf, CachedDirectory>,
+    /// Directories seen during this run, which replace the previous ones on
+    /// save so deleted paths don't linger in the cache
+    current: HashMap<PathBuf, CachedDirectory>,
+}
+
+impl SizeCache {
+    /// Loads the cache from `file`, starting out empty if it doesn't exist yet
+    pub fn load(file: &Path) -> anyhow::Result<Self> {
+        let reader = match File::open(file) {
+            Ok(reader) => BufReader::new(reader),
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(error) => {
+                return Err(error).context(format!("Failed opening cache {}", file.display()))
+            }
+        };
+
+        let previous = serde_json::from_reader(reader).context(format!(
+            "Failed reading cache {}. Delete it to start over",
+            file.display()
+        ))?;
+
+        Ok(Self {
+            previous,
+            current: HashMap::new(),
+        })
+    }
+
+    /// The contents of the directory at `path`, taken from the cache if it
+    /// hasn't been modified since it was cached, or else `read`
+    pub fn contents(
+        &mut self,
+        path: PathBuf,
+        modified: SystemTime,
+        read: impl FnOnce() -> anyhow::Result<DirectoryContents>,
+    ) -> anyhow::Result<DirectoryContents> {
+        let contents = match self.previous.remove(&path) {
+            Some(cached) if cached.modified == modified => cached.contents,
+            _ => read()?,
+        };
+        self.current.insert(
+            path,
+            CachedDirectory {
+                modified,
+                contents: contents.clone(),
+            },
+        );
+        Ok(contents)
+    }
+
+    pub fn save(&self, file: &Path) -> anyhow::Result<()> {
+        let writer = BufWriter::new(
+            File::create(file).context(format!("Failed creating cache {}", file.display()))?,
+        );
+        serde_json::to_writer(writer, &self.current)
+            .context(format!("Failed writing cache {}", file.display()))
+    }
+}
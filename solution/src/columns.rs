@@ -0,0 +1,56 @@
+use clap::ValueEnum;
+
+/// A column of the listing, as chosen with `--columns`
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Column {
+    /// Permissions, owner and group, like `--long`
+    Permissions,
+    Size,
+    /// A bar scaled to the largest file listed, like `--histogram size`
+    Bar,
+    /// How many entries directories directly contain
+    Entries,
+    /// How many lines text files have, like `--count-lines`
+    Lines,
+    /// A checksum of files' contents, like `--hash`
+    Hash,
+    /// When entries were last modified, like `--modified`
+    Modified,
+    /// When entries' metadata last changed, like `--ctime`
+    Changed,
+    Name,
+}
+
+/// The headers of the `Entries` and `Lines` columns, whose counts are padded
+/// to be as wide as them
+pub const ENTRIES_HEADER: &str = "Entries";
+pub const LINES_HEADER: &str = "Lines";
+
+impl Column {
+    /// The columns shown without `--columns`, with those asked for by the flags
+    /// for single columns in the order they've always been shown in
+    pub fn defaults(
+        long: bool,
+        bar: bool,
+        entries: bool,
+        lines: bool,
+        hash: bool,
+        modified: bool,
+        changed: bool,
+    ) -> Vec<Self> {
+        [
+            (long, Self::Permissions),
+            (true, Self::Size),
+            (bar, Self::Bar),
+            (entries, Self::Entries),
+            (lines, Self::Lines),
+            (hash, Self::Hash),
+            (modified, Self::Modified),
+            (changed, Self::Changed),
+            (true, Self::Name),
+        ]
+        .into_iter()
+        .filter_map(|(shown, column)| shown.then_some(column))
+        .collect()
+    }
+}
@@ -0,0 +1,36 @@
+use std::{fs::File, io::Read, path::Path};
+
+use anyhow::Context;
+use regex::bytes::Regex;
+
+/// Matches the contents of files against `--match-content`
+pub struct ContentMatcher {
+    regex: Regex,
+    /// How much of every file is searched
+    max_bytes: u64,
+}
+
+impl ContentMatcher {
+    pub fn new(regex: &str, max_bytes: u64) -> anyhow::Result<Self> {
+        let regex = Regex::new(regex).context(format!("Invalid regex {regex}"))?;
+        Ok(Self { regex, max_bytes })
+    }
+
+    /// Whether the start of the file at `path` matches. Files that can't be
+    /// read or look binary, by containing a NUL byte, never match
+    pub fn matches(&self, path: &Path) -> bool {
+        let Ok(file) = File::open(path) else {
+            return false;
+        };
+        let mut contents = Vec::new();
+        if file
+            .take(self.max_bytes)
+            .read_to_end(&mut contents)
+            .is_err()
+        {
+            return false;
+        }
+
+        !contents.contains(&0) && self.regex.is_match(&contents)
+    }
+}
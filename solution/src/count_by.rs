@@ -0,0 +1,136 @@
+use std::{collections::HashMap, io::Write};
+
+use clap::ValueEnum;
+
+use crate::{
+    entry::Entry,
+    filter,
+    format::{FormatSize, SIZE_WIDTH},
+    record,
+    theme::Theme,
+};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum CountBy {
+    /// The extension of files, with directories in a group of their own
+    Extension,
+    /// Whether entries are files, directories, symlinks or something else
+    Type,
+    /// The user owning entries, by name where it has one
+    Owner,
+    /// The day entries were last modified, in UTC
+    MtimeDay,
+}
+
+#[derive(Default)]
+struct Group {
+    count: usize,
+    size: u64,
+}
+
+/// How many entries, and how large in total, there are of every value of a
+/// field, printed by `--count-by` instead of the entries themselves
+pub struct Groups {
+    field: CountBy,
+    groups: HashMap<String, Group>,
+}
+
+impl Groups {
+    pub fn new(field: CountBy) -> Self {
+        Self {
+            field,
+            groups: HashMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, entry: &Entry) {
+        let key = match self.field {
+            CountBy::Extension if entry.metadata.is_dir() => "(directory)".into(),
+            CountBy::Extension => {
+                filter::extension(&entry.name, false).unwrap_or_else(|| "(none)".into())
+            }
+            CountBy::Type => record::kind(entry.metadata.file_type()).into(),
+            CountBy::Owner => owner(entry),
+            CountBy::MtimeDay => match entry.metadata.modified() {
+                Ok(modified) => chrono::DateTime::<chrono::Utc>::from(modified)
+                    .format("%Y-%m-%d")
+                    .to_string(),
+                Err(_) => "(unknown)".into(),
+            },
+        };
+
+        let group = self.groups.entry(key).or_default();
+        group.count += 1;
+        group.size += entry.size;
+    }
+
+    /// Writes a table of the groups, the largest first by total size if
+    /// `by_size` and by count otherwise
+    pub fn write(
+        self,
+        mut output: impl Write,
+        by_size: bool,
+        theme: &Theme,
+    ) -> std::io::Result<()> {
+        let mut groups: Vec<_> = self.groups.into_iter().collect();
+        groups.sort_by(|(a_key, a), (b_key, b)| {
+            let order = if by_size {
+                b.size.cmp(&a.size)
+            } else {
+                b.count.cmp(&a.count)
+            };
+            order.then_with(|| a_key.cmp(b_key))
+        });
+
+        writeln!(
+            output,
+            "{:>2}{}\t{:>5}{}\t{}",
+            "",
+            theme.header.paint("Count"),
+            "",
+            theme.header.paint("Size"),
+            theme.header.paint("Group")
+        )?;
+        for (key, group) in groups {
+            let size = FormatSize {
+                size: group.size,
+                directory: None,
+                width: SIZE_WIDTH,
+                theme,
+            };
+            let key = match self.field {
+                CountBy::Owner => owner_name(key),
+                _ => key,
+            };
+            writeln!(output, "{:>7}\t{size}\t{key}", group.count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn owner(entry: &Entry) -> String {
+    use std::os::unix::fs::MetadataExt;
+    entry.metadata.uid().to_string()
+}
+
+#[cfg(not(unix))]
+fn owner(_entry: &Entry) -> String {
+    "(unknown)".into()
+}
+
+/// The name of the user with the uid `owner`, or the uid itself if it has no
+/// name. Names are only looked up once per group rather than for every entry
+#[cfg(unix)]
+fn owner_name(owner: String) -> String {
+    owner
+        .parse()
+        .ok()
+        .and_then(users::get_user_by_uid)
+        .map_or(owner, |user| user.name().to_string_lossy().into_owned())
+}
+
+#[cfg(not(unix))]
+fn owner_name(owner: String) -> String {
+    owner
+}
@@ -0,0 +1,261 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::Metadata,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use walkdir::WalkDir;
+
+use crate::{
+    cache::{Child, DirectoryContents, SizeCache},
+    stats,
+};
+
+/// The total size of the files within every directory below a root, like `du`
+pub struct DirectorySizes(HashMap<PathBuf, u64>);
+
+impl DirectorySizes {
+    /// Walks everything below `root` (which resolves to `absolute_root`),
+    /// summing file sizes bottom-up into their ancestor directories. With
+    /// `deduplicate_hardlinks`, files with several hard links are only counted
+    /// the first time they're found, like `du` does. With a `cache`,
+    /// directories that haven't been modified since are neither listed nor
+    /// have their files stat-ed
+    pub fn compute(
+        root: &Path,
+        absolute_root: &Path,
+        cache: Option<&mut SizeCache>,
+        deduplicate_hardlinks: bool,
+    ) -> anyhow::Result<Self> {
+        let mut sizes = HashMap::new();
+        let mut seen_files = HashSet::new();
+        if let Some(cache) = cache.filter(|_| root.is_dir()) {
+            sum_cached(
+                root,
+                absolute_root,
+                cache,
+                deduplicate_hardlinks,
+                &mut seen_files,
+                &mut sizes,
+            )?;
+            return Ok(Self(sizes));
+        }
+
+        // The directories leading to the current entry, with the size summed so far
+        let mut ancestors: Vec<(PathBuf, u64)> = Vec::new();
+
+        for entry in WalkDir::new(root) {
+            let entry = entry.context("Error getting file entry")?;
+
+            while ancestors.len() > entry.depth() {
+                // SAFETY: We just checked that there is an ancestor to pop
+                let (directory, size) = ancestors.pop().unwrap();
+                if let Some((_, parent_size)) = ancestors.last_mut() {
+                    *parent_size += size;
+                }
+                sizes.insert(directory, size);
+            }
+
+            if entry.file_type().is_dir() {
+                ancestors.push((entry.into_path(), 0));
+                continue;
+            }
+
+            let metadata = stats::stat_call(|| entry.metadata()).context(format!(
+                "Failed extracting metadata for {}. Perhaps you are missing permissions?",
+                entry.path().display()
+            ))?;
+            if deduplicate_hardlinks && !is_first_link(&metadata, &mut seen_files) {
+                continue;
+            }
+            if let Some((_, parent_size)) = ancestors.last_mut() {
+                *parent_size += metadata.len();
+            }
+        }
+
+        while let Some((directory, size)) = ancestors.pop() {
+            if let Some((_, parent_size)) = ancestors.last_mut() {
+                *parent_size += size;
+            }
+            sizes.insert(directory, size);
+        }
+
+        Ok(Self(sizes))
+    }
+
+    /// Like [`DirectorySizes::compute`] for each of the directories among
+    /// `paths`, which can't use the cache as there's no single root
+    pub fn compute_each(paths: &[PathBuf], deduplicate_hardlinks: bool) -> anyhow::Result<Self> {
+        let mut sizes = HashMap::new();
+        for path in paths.iter().filter(|path| path.is_dir()) {
+            sizes.extend(Self::compute(path, path, None, deduplicate_hardlinks)?.0);
+        }
+        Ok(Self(sizes))
+    }
+
+    /// The total size of the directory at `path`, if it was below the root
+    pub fn get(&self, path: &Path) -> Option<u64> {
+        self.0.get(path).copied()
+    }
+}
+
+/// Sums the sizes below the directory at `path` (which resolves to
+/// `absolute`) into `sizes`, reusing the cached contents of every directory
+/// that hasn't been modified since it was cached, and returns its total
+fn sum_cached(
+    path: &Path,
+    absolute: &Path,
+    cache: &mut SizeCache,
+    deduplicate_hardlinks: bool,
+    seen_files: &mut HashSet<(u64, u64)>,
+    sizes: &mut HashMap<PathBuf, u64>,
+) -> anyhow::Result<u64> {
+    let metadata = stats::stat_call(|| path.metadata()).context(format!(
+        "Failed extracting metadata for {}. Perhaps you are missing permissions?",
+        path.display()
+    ))?;
+    let contents = match metadata.modified() {
+        Ok(modified) => cache.contents(absolute.to_path_buf(), modified, || read_contents(path))?,
+        Err(_) => read_contents(path)?,
+    };
+
+    let mut size = contents.files_size;
+    for child in &contents.children {
+        size += match child {
+            Child::Directory(name) => sum_cached(
+                &path.join(name),
+                &absolute.join(name),
+                cache,
+                deduplicate_hardlinks,
+                seen_files,
+                sizes,
+            )?,
+            Child::HardLink {
+                device,
+                inode,
+                size,
+            } if !deduplicate_hardlinks || seen_files.insert((*device, *inode)) => *size,
+            Child::HardLink { .. } => 0,
+        };
+    }
+    sizes.insert(path.to_path_buf(), size);
+    Ok(size)
+}
+
+/// Lists the directory at `path`, stat-ing each of its files
+fn read_contents(path: &Path) -> anyhow::Result<DirectoryContents> {
+    let mut contents = DirectoryContents::default();
+    let entries = std::fs::read_dir(path).context(format!("Failed listing {}", path.display()))?;
+    for entry in entries {
+        let entry = entry.context("Error getting file entry")?;
+        let file_type = entry
+            .file_type()
+            .context(format!("Failed reading {}", entry.path().display()))?;
+        if file_type.is_dir() {
+            contents
+                .children
+                .push(Child::Directory(entry.file_name().into()));
+            continue;
+        }
+
+        let metadata = stats::stat_call(|| entry.metadata()).context(format!(
+            "Failed extracting metadata for {}. Perhaps you are missing permissions?",
+            entry.path().display()
+        ))?;
+        match hard_link(&metadata) {
+            Some(link) => contents.children.push(link),
+            None => contents.files_size += metadata.len(),
+        }
+    }
+    Ok(contents)
+}
+
+/// The file with `metadata`, if it has several hard links
+#[cfg(unix)]
+fn hard_link(metadata: &Metadata) -> Option<Child> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.nlink() > 1).then(|| Child::HardLink {
+        device: metadata.dev(),
+        inode: metadata.ino(),
+        size: metadata.len(),
+    })
+}
+
+#[cfg(not(unix))]
+fn hard_link(_metadata: &Metadata) -> Option<Child> {
+    None
+}
+
+/// Whether this is the first time the file with `metadata` is seen, rather
+/// than another hard link to a file in `seen_files`
+#[cfg(unix)]
+pub fn is_first_link(metadata: &Metadata, seen_files: &mut HashSet<(u64, u64)>) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    metadata.nlink() <= 1 || seen_files.insert((metadata.dev(), metadata.ino()))
+}
+
+/// Hard links can't be told apart from separate files without inode numbers,
+/// so every link is counted on other platforms
+#[cfg(not(unix))]
+pub fn is_first_link(_metadata: &Metadata, _seen_files: &mut HashSet<(u64, u64)>) -> bool {
+    true
+}
+
+/// The total size of everything at `path`, which may also be a single file
+pub fn total_size(path: &Path, deduplicate_hardlinks: bool) -> anyhow::Result<u64> {
+    let sizes = DirectorySizes::compute(path, path, None, deduplicate_hardlinks)?;
+    match sizes.get(path) {
+        Some(size) => Ok(size),
+        None => Ok(stats::stat_call(|| path.symlink_metadata())
+            .context(format!("Failed reading {}", path.display()))?
+            .len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, File},
+        time::{Duration, SystemTime},
+    };
+
+    use super::*;
+
+    /// The sizes below `root`, using and then updating the cache in `cache_file`
+    fn cached_sizes(root: &Path, cache_file: &Path) -> DirectorySizes {
+        let mut cache = SizeCache::load(cache_file).unwrap();
+        let sizes = DirectorySizes::compute(root, root, Some(&mut cache), false).unwrap();
+        cache.save(cache_file).unwrap();
+        sizes
+    }
+
+    #[test]
+    fn cache_reuses_unmodified_directories() {
+        let root = tempfile::tempdir().unwrap();
+        let cache_directory = tempfile::tempdir().unwrap();
+        let cache_file = &cache_directory.path().join("cache.json");
+        let directory = root.path().join("directory");
+        fs::create_dir(&directory).unwrap();
+        fs::write(directory.join("file"), "a").unwrap();
+        assert_eq!(
+            cached_sizes(root.path(), cache_file).get(&directory),
+            Some(1)
+        );
+
+        // Changing a file in place doesn't modify its directory
+        fs::write(directory.join("file"), "abc").unwrap();
+        assert_eq!(
+            cached_sizes(root.path(), cache_file).get(&directory),
+            Some(1)
+        );
+
+        File::open(&directory)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        let sizes = cached_sizes(root.path(), cache_file);
+        assert_eq!(sizes.get(&directory), Some(3));
+        assert_eq!(sizes.get(root.path()), Some(3));
+    }
+}
@@ -0,0 +1,117 @@
+use std::{collections::HashMap, io::Write, path::PathBuf};
+
+use bytesize::ByteSize;
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::{
+    hash::{hash_file, hash_file_start, HashAlgorithm},
+    theme::Theme,
+};
+
+/// Files with the same contents, found by `dupes`
+pub struct DuplicateSet {
+    /// The size of each of the files
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl DuplicateSet {
+    /// The space that would be freed by keeping only one of the files
+    pub fn wasted(&self) -> u64 {
+        self.size * (self.paths.len() as u64 - 1)
+    }
+}
+
+/// Finds the sets of `files`, given with their sizes, that have the same
+/// contents, the ones wasting the most space first. Only files with the same
+/// size as another are hashed, and empty files are left out. With `limit`, only
+/// the first `limit` bytes of files are hashed, so files that differ after them
+/// are reported as duplicates too.
+///
+/// Files that can't be read are skipped with a warning, and hashing is counted
+/// on `progress`
+pub fn find(
+    files: Vec<(PathBuf, u64)>,
+    algorithm: HashAlgorithm,
+    limit: Option<u64>,
+    progress: &ProgressBar,
+) -> Vec<DuplicateSet> {
+    let mut by_size: HashMap<u64, Vec<PathBuf>> = HashMap::new();
+    for (path, size) in files {
+        if size > 0 {
+            by_size.entry(size).or_default().push(path);
+        }
+    }
+    by_size.retain(|_, paths| paths.len() > 1);
+
+    progress.set_position(0);
+    progress.set_length(by_size.values().map(|paths| paths.len() as u64).sum());
+    // SAFETY: The template is static and known to be valid
+    progress.set_style(ProgressStyle::with_template("{bar} {pos}/{len} files hashed").unwrap());
+
+    let mut sets = Vec::new();
+    for (size, paths) in by_size {
+        let mut by_hash: HashMap<String, Vec<PathBuf>> = HashMap::new();
+        for path in paths {
+            let hash = match limit {
+                Some(limit) => hash_file_start(&path, algorithm, limit),
+                None => hash_file(&path, algorithm),
+            };
+            progress.inc(1);
+            match hash {
+                Ok(hash) => by_hash.entry(hash).or_default().push(path),
+                Err(error) => progress.suspend(|| {
+                    eprintln!(
+                        "{} Failed hashing {}: {error}",
+                        "warning:".yellow().bold(),
+                        path.display()
+                    )
+                }),
+            }
+        }
+
+        sets.extend(
+            by_hash
+                .into_values()
+                .filter(|paths| paths.len() > 1)
+                .map(|mut paths| {
+                    paths.sort();
+                    DuplicateSet { size, paths }
+                }),
+        );
+    }
+
+    sets.sort_by(|a, b| {
+        b.wasted()
+            .cmp(&a.wasted())
+            .then_with(|| a.paths.cmp(&b.paths))
+    });
+    sets
+}
+
+/// Writes every set with the space it wastes, followed by the total
+pub fn write(sets: &[DuplicateSet], mut output: impl Write, theme: &Theme) -> std::io::Result<()> {
+    for set in sets {
+        writeln!(
+            output,
+            "{} wasted by {} copies of {}",
+            theme.size.paint(&ByteSize(set.wasted()).to_string()),
+            set.paths.len(),
+            theme.size.paint(&ByteSize(set.size).to_string())
+        )?;
+        for path in &set.paths {
+            writeln!(output, "  {}", theme.file.paint(&path.to_string_lossy()))?;
+        }
+        writeln!(output)?;
+    }
+
+    let wasted = sets.iter().map(DuplicateSet::wasted).sum();
+    writeln!(
+        output,
+        "{} {} wasted by {} sets of duplicates",
+        theme.header.paint("Total:"),
+        theme.size.paint(&ByteSize(wasted).to_string()),
+        sets.len()
+    )
+}
@@ -0,0 +1,377 @@
+use std::{
+    fs::{File, Metadata},
+    io::Read,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use walkdir::{DirEntry, WalkDir};
+
+use crate::{
+    hash::{self, HashAlgorithm},
+    stats,
+};
+
+/// An entry that passed all filters and is about to be listed
+pub struct Entry {
+    pub path: PathBuf,
+    /// The name shown in the listing, usually the last part of the path
+    pub name: String,
+    pub depth: usize,
+    pub metadata: Metadata,
+    /// The size shown in the listing, which for directories is the total size
+    /// of their contents with `--du`
+    pub size: u64,
+    /// How many entries a directory directly contains, when it was needed
+    pub children: Option<usize>,
+    /// What a symlink points to, if it could be read
+    pub link_target: Option<PathBuf>,
+    /// The most recently modified file within a directory and when it was
+    /// modified, with `--newest-in-dir`
+    pub newest_file: Option<(PathBuf, SystemTime)>,
+    /// How many lines a text file has, with `--count-lines`
+    pub lines: Option<u64>,
+    /// The connectors drawn in front of the name with `--tree`, like `│   ├── `
+    pub tree_prefix: Option<String>,
+    /// The checksum of a file's contents, with `--hash`
+    pub hash: Option<String>,
+}
+
+impl Entry {
+    pub fn new(entry: DirEntry) -> anyhow::Result<Self> {
+        Self::found(
+            entry.path().to_path_buf(),
+            entry.depth(),
+            entry.path_is_symlink(),
+            || entry.metadata(),
+        )
+    }
+
+    /// The entry a walk found at `path`, reading its `metadata` once. That's
+    /// what a followed symlink points to, though `path_is_symlink` still
+    /// tells it was a link
+    pub(crate) fn found<E>(
+        path: PathBuf,
+        depth: usize,
+        path_is_symlink: bool,
+        metadata: impl FnOnce() -> Result<Metadata, E>,
+    ) -> anyhow::Result<Self>
+    where
+        E: std::error::Error + Send + Sync + 'static,
+    {
+        let metadata = stats::time_metadata(|| stats::stat_call(metadata)).context(format!(
+            "Failed extracting metadata for {}. Perhaps you are missing permissions?",
+            path.display()
+        ))?;
+        // SAFETY: We can safely unwrap here as we know the path contains at least one part (e.g. `.` or `./thing`, or so on)
+        let name = path
+            .iter()
+            .next_back()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+
+        let link_target = path_is_symlink
+            .then(|| std::fs::read_link(&path).ok())
+            .flatten();
+
+        Ok(Self {
+            size: metadata.len(),
+            children: None,
+            link_target,
+            newest_file: None,
+            lines: None,
+            tree_prefix: None,
+            hash: None,
+            depth,
+            path,
+            name,
+            metadata,
+        })
+    }
+
+    /// The entry at `path` on its own, outside of any walk
+    pub fn at(path: &Path) -> anyhow::Result<Self> {
+        Self::at_following(path, true)
+    }
+
+    /// Like [`Entry::at`], but for a symlink at `path` this is the link itself
+    /// rather than what it points to
+    pub fn link_at(path: &Path) -> anyhow::Result<Self> {
+        Self::at_following(path, false)
+    }
+
+    fn at_following(path: &Path, follow: bool) -> anyhow::Result<Self> {
+        let entry = WalkDir::new(path)
+            .follow_root_links(follow)
+            .max_depth(0)
+            .into_iter()
+            .next()
+            .context(format!("Failed reading {}", path.display()))?
+            .context("Error getting file entry")?;
+        Self::new(entry)
+    }
+
+    /// Counts the entries directly within this directory, hidden ones
+    /// included. This reads the whole directory, so it's not free
+    pub fn count_children(&mut self) -> anyhow::Result<()> {
+        let children = std::fs::read_dir(&self.path)
+            .context(format!("Failed reading directory {}", self.path.display()))?
+            .count();
+        self.children = Some(children);
+        Ok(())
+    }
+
+    /// Counts the newlines in this file, reading it a block at a time. Files
+    /// that look binary, by containing a NUL byte, are left without a count
+    pub fn count_lines(&mut self) -> anyhow::Result<()> {
+        let mut file =
+            File::open(&self.path).context(format!("Failed opening {}", self.path.display()))?;
+        let mut buffer = [0; 64 * 1024];
+        let mut lines = 0;
+
+        loop {
+            let read = file
+                .read(&mut buffer)
+                .context(format!("Failed reading {}", self.path.display()))?;
+            let block = &buffer[..read];
+            if block.is_empty() {
+                break;
+            }
+            if block.contains(&0) {
+                return Ok(());
+            }
+            lines += block.iter().filter(|&&byte| byte == b'\n').count() as u64;
+        }
+
+        self.lines = Some(lines);
+        Ok(())
+    }
+
+    /// Hashes the contents of this file with `algorithm`
+    pub fn hash(&mut self, algorithm: HashAlgorithm) -> anyhow::Result<()> {
+        let hash = hash::hash_file(&self.path, algorithm)
+            .context(format!("Failed hashing {}", self.path.display()))?;
+        self.hash = Some(hash);
+        Ok(())
+    }
+
+    /// When the entry's inode last changed, e.g. by writing to it or changing
+    /// its permissions or owner. This is Unix's ctime, which despite the name
+    /// isn't when the entry was created. Always `None` on other platforms
+    #[cfg(unix)]
+    pub fn changed(&self) -> Option<SystemTime> {
+        use std::{os::unix::fs::MetadataExt, time::Duration};
+
+        let seconds = u64::try_from(self.metadata.ctime()).ok()?;
+        let nanoseconds = u32::try_from(self.metadata.ctime_nsec()).ok()?;
+        SystemTime::UNIX_EPOCH.checked_add(Duration::new(seconds, nanoseconds))
+    }
+
+    #[cfg(not(unix))]
+    pub fn changed(&self) -> Option<SystemTime> {
+        None
+    }
+
+    /// Whether the entry claims to have been modified before it was created,
+    /// which ordinary writes can't cause. `false` if either time is unknown
+    pub fn modified_before_created(&self) -> bool {
+        match (self.metadata.modified(), self.metadata.created()) {
+            (Ok(modified), Ok(created)) => modified < created,
+            _ => false,
+        }
+    }
+}
+
+/// Whether the directory at `path` is a mount point, being on another device
+/// than its parent. Always `false` on other platforms than Unix
+#[cfg(unix)]
+pub fn is_mount_point(path: &Path, metadata: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    else {
+        return false;
+    };
+    metadata.is_dir()
+        && stats::stat_call(|| std::fs::metadata(parent))
+            .is_ok_and(|parent| parent.dev() != metadata.dev())
+}
+
+#[cfg(not(unix))]
+pub fn is_mount_point(_path: &Path, _metadata: &Metadata) -> bool {
+    false
+}
+
+/// Sets the `tree_prefix` of every entry to box-drawing connectors showing
+/// which entries are siblings, like `tree` does.
+///
+/// `entries` must be in walk order, i.e. every directory directly followed by
+/// its descendants.
+pub fn draw_tree(entries: &mut [Entry]) {
+    let Some(min_depth) = entries.iter().map(|entry| entry.depth).min() else {
+        return;
+    };
+
+    // Whether every entry is the last of its siblings, found walking
+    // backwards by remembering which depths have a sibling coming up
+    let mut is_last = vec![false; entries.len()];
+    let mut has_next_sibling: Vec<bool> = Vec::new();
+    for (index, entry) in entries.iter().enumerate().rev() {
+        let level = entry.depth - min_depth;
+        has_next_sibling.resize(level + 1, false);
+        is_last[index] = !has_next_sibling[level];
+        has_next_sibling[level] = true;
+    }
+
+    // Whether each ancestor of the current entry was the last of its siblings,
+    // which decides whether its line continues down past the current entry
+    let mut ancestors_last: Vec<bool> = Vec::new();
+    for (index, entry) in entries.iter_mut().enumerate() {
+        let level = entry.depth - min_depth;
+        ancestors_last.truncate(level);
+        let mut prefix: String = ancestors_last
+            .iter()
+            .map(|&last| if last { "    " } else { "│   " })
+            .collect();
+        prefix.push_str(if is_last[index] {
+            "└── "
+        } else {
+            "├── "
+        });
+        entry.tree_prefix = Some(prefix);
+        ancestors_last.resize(level, false);
+        ancestors_last.push(is_last[index]);
+    }
+}
+
+/// Collapses chains of directories that only contain a single directory into
+/// one entry named `a/b/c`, like GitHub's tree view does.
+///
+/// `entries` must be in walk order, i.e. every directory directly followed by
+/// its descendants.
+pub fn flatten_directory_chains(entries: Vec<Entry>) -> Vec<Entry> {
+    // Index one past the last descendant of each entry
+    let mut subtree_ends = vec![entries.len(); entries.len()];
+    let mut ancestors: Vec<usize> = Vec::new();
+    for (index, entry) in entries.iter().enumerate() {
+        while let Some(&ancestor) = ancestors.last() {
+            if entries[ancestor].depth < entry.depth {
+                break;
+            }
+            subtree_ends[ancestor] = index;
+            ancestors.pop();
+        }
+        ancestors.push(index);
+    }
+
+    let has_single_directory_child = |index: usize| {
+        let child = index + 1;
+        entries[index].metadata.is_dir()
+            && child < subtree_ends[index]
+            && subtree_ends[child] == subtree_ends[index]
+            && entries[child].metadata.is_dir()
+    };
+
+    // For every chain, the index of its last directory, which the first
+    // directory of the chain takes the path and metadata from
+    let mut chain_ends: Vec<usize> = (0..entries.len()).collect();
+    let mut collapsed = vec![false; entries.len()];
+    let mut depth_reductions = vec![0; entries.len()];
+
+    for index in 0..entries.len() {
+        if collapsed[index] {
+            continue;
+        }
+
+        let mut end = index;
+        while has_single_directory_child(end) {
+            end += 1;
+            collapsed[end] = true;
+            for reduction in &mut depth_reductions[end + 1..subtree_ends[end]] {
+                *reduction += 1;
+            }
+        }
+        chain_ends[index] = end;
+    }
+
+    let mut entries = entries.into_iter().enumerate();
+    let mut flattened = Vec::new();
+
+    while let Some((index, first)) = entries.next() {
+        let depth = first.depth - depth_reductions[index];
+        let mut names = vec![first.name.clone()];
+        let mut last = first;
+
+        // Chains are contiguous in walk order, so the rest of it follows directly
+        for (_, entry) in entries.by_ref().take(chain_ends[index] - index) {
+            names.push(entry.name.clone());
+            last = entry;
+        }
+
+        flattened.push(Entry {
+            name: names.join("/"),
+            depth,
+            ..last
+        });
+    }
+
+    flattened
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::explorer::Explorer;
+
+    /// The names and depths of what's below `root` once flattened
+    fn flattened(root: &Path) -> Vec<(String, usize)> {
+        let entries = Explorer::new(root)
+            .sort_by_file_name(true)
+            .walk()
+            .collect::<anyhow::Result<Vec<_>>>()
+            .unwrap();
+        flatten_directory_chains(entries)
+            .into_iter()
+            .map(|entry| (entry.name, entry.depth))
+            .collect()
+    }
+
+    #[test]
+    fn a_deep_chain_of_single_directories_is_one_line() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("a/b/c/d")).unwrap();
+        fs::write(root.path().join("a/b/c/d/file.txt"), "").unwrap();
+        fs::write(root.path().join("other.txt"), "").unwrap();
+
+        assert_eq!(
+            flattened(root.path()),
+            [
+                ("a/b/c/d".to_string(), 1),
+                ("file.txt".to_string(), 2),
+                ("other.txt".to_string(), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn a_chain_ends_where_a_directory_has_several_entries() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("a/b/c")).unwrap();
+        fs::create_dir_all(root.path().join("a/b/d")).unwrap();
+
+        assert_eq!(
+            flattened(root.path()),
+            [
+                ("a/b".to_string(), 1),
+                ("c".to_string(), 2),
+                ("d".to_string(), 2),
+            ]
+        );
+    }
+}
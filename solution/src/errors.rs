@@ -0,0 +1,58 @@
+use std::{collections::BTreeMap, path::PathBuf};
+
+use crate::{follow::WalkError, theme::Theme};
+
+/// Errors skipped with `--skip-errors`, collected by `--summarize-errors` to
+/// be reported together at the end
+#[derive(Default)]
+pub struct ErrorSummary {
+    /// The paths that failed, by the kind of error
+    by_kind: BTreeMap<String, Vec<PathBuf>>,
+}
+
+impl ErrorSummary {
+    pub fn add(&mut self, error: &anyhow::Error) {
+        let (kind, path) = describe(error);
+        self.by_kind
+            .entry(kind)
+            .or_default()
+            .push(path.unwrap_or_default());
+    }
+
+    /// Prints how many errors of every kind there were to stderr, followed by
+    /// the paths of each kind
+    pub fn report(&self, theme: &Theme) {
+        if self.by_kind.is_empty() {
+            return;
+        }
+
+        let counts: Vec<String> = self
+            .by_kind
+            .iter()
+            .map(|(kind, paths)| format!("{} {kind}", paths.len()))
+            .collect();
+        eprintln!("{} {}", theme.error.paint("errors:"), counts.join(", "));
+
+        for (kind, paths) in &self.by_kind {
+            eprintln!("{kind}:");
+            for path in paths {
+                eprintln!("  {}", path.display());
+            }
+        }
+    }
+}
+
+/// The kind of `error`, like `permission denied`, and the path it's about if
+/// it came from the walk
+pub fn describe(error: &anyhow::Error) -> (String, Option<PathBuf>) {
+    let Some(walk_error) = error.downcast_ref::<WalkError>() else {
+        return ("other".into(), None);
+    };
+    let path = walk_error.path().map(PathBuf::from);
+    let kind = match (walk_error.io_error(), walk_error.loop_ancestor()) {
+        (Some(io_error), _) => io_error.kind().to_string(),
+        (None, Some(_)) => "symlink loop".into(),
+        (None, None) => "other".into(),
+    };
+    (kind, path)
+}
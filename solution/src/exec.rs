@@ -0,0 +1,129 @@
+use std::{
+    ffi::OsString,
+    fmt::Display,
+    path::Path,
+    process::{Command, ExitStatus},
+};
+
+use anyhow::Context;
+
+/// Placeholder replaced by the entry's path in `--exec` arguments
+const PATH_PLACEHOLDER: &str = "{}";
+
+/// A command given to `--exec`, run once per listed entry
+pub struct CommandTemplate(Vec<String>);
+
+impl CommandTemplate {
+    pub fn new(args: Vec<String>) -> Self {
+        Self(args)
+    }
+
+    /// Builds the program and arguments to run for `path`, replacing every
+    /// `{}` with it. If no argument contains a placeholder the path is
+    /// appended as the last argument instead. Paths that aren't valid UTF-8
+    /// are passed on as they are
+    pub fn substitute(&self, path: &Path) -> Vec<OsString> {
+        let mut args: Vec<OsString> = self
+            .0
+            .iter()
+            .map(|arg| {
+                let mut substituted = OsString::new();
+                for (index, part) in arg.split(PATH_PLACEHOLDER).enumerate() {
+                    if index > 0 {
+                        substituted.push(path);
+                    }
+                    substituted.push(part);
+                }
+                substituted
+            })
+            .collect();
+
+        if !self.0.iter().any(|arg| arg.contains(PATH_PLACEHOLDER)) {
+            args.push(path.into());
+        }
+
+        args
+    }
+
+    /// Runs the command for `path`, waiting for it to finish
+    pub fn run(&self, path: &Path) -> anyhow::Result<ExitStatus> {
+        let args = self.substitute(path);
+        // SAFETY: clap requires at least one value for `--exec`
+        let (program, args) = args.split_first().unwrap();
+        Command::new(program)
+            .args(args)
+            .status()
+            .context(format!("Failed running {}", program.to_string_lossy()))
+    }
+}
+
+/// Displays a substituted command line, quoting arguments where a shell would
+/// need it. Arguments that aren't valid UTF-8 are shown lossily
+pub struct FormatCommandLine<'args>(pub &'args [OsString]);
+
+impl<'args> Display for FormatCommandLine<'args> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (index, arg) in self.0.iter().enumerate() {
+            if index > 0 {
+                f.write_str(" ")?;
+            }
+
+            let arg = arg.to_string_lossy();
+            let needs_quoting = arg.is_empty()
+                || arg
+                    .chars()
+                    .any(|c| c.is_whitespace() || "'\"\\$`&|;<>()*?!#~".contains(c));
+
+            if needs_quoting {
+                f.write_fmt(format_args!("'{}'", arg.replace('\'', r"'\''")))?;
+            } else {
+                f.write_str(&arg)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(args: &[&str]) -> CommandTemplate {
+        CommandTemplate::new(args.iter().map(|arg| arg.to_string()).collect())
+    }
+
+    #[test]
+    fn substitute_replaces_every_placeholder() {
+        let args = template(&["cp", "{}", "{}.bak"]).substitute(Path::new("./a b.txt"));
+        assert_eq!(args, ["cp", "./a b.txt", "./a b.txt.bak"]);
+    }
+
+    #[test]
+    fn substitute_appends_the_path_without_a_placeholder() {
+        let args = template(&["wc", "-l"]).substitute(Path::new("./file"));
+        assert_eq!(args, ["wc", "-l", "./file"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn substitute_keeps_paths_that_arent_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = Path::new(OsStr::from_bytes(b"./caf\xe9"));
+        let args = template(&["mv", "{}", "{}.old"]).substitute(path);
+        assert_eq!(args[1].as_bytes(), b"./caf\xe9");
+        assert_eq!(args[2].as_bytes(), b"./caf\xe9.old");
+    }
+
+    #[test]
+    fn format_command_line_quotes_what_a_shell_would_interpret() {
+        let args: Vec<OsString> = ["echo", "plain", "", "a b", "it's", "$HOME"]
+            .into_iter()
+            .map(OsString::from)
+            .collect();
+        assert_eq!(
+            FormatCommandLine(&args).to_string(),
+            r"echo plain '' 'a b' 'it'\''s' '$HOME'"
+        );
+    }
+}
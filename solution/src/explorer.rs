@@ -0,0 +1,750 @@
+use std::{
+    ffi::OsStr,
+    fs::Metadata,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use walkdir::WalkDir;
+
+use crate::{
+    content::ContentMatcher,
+    du::DirectorySizes,
+    entry::Entry,
+    filter::{self, NameFilter, PathFilter, SizeRange},
+    follow::{FollowDepth, WalkError, Walked},
+    gitignore::IgnoreRules,
+    hash::HashAlgorithm,
+    is_hidden_entry,
+    kind::FileKind,
+    newest::NewestModifications,
+    parallel::{self, ParallelMap},
+    paths::{absolute_entry_path, resolved_path},
+    stats,
+};
+
+/// Decides whether an entry the walk finds, and what's within it, is kept
+type Predicate<'a> = Box<dyn Fn(&Candidate) -> bool + Sync + 'a>;
+
+/// Reports a problem that doesn't fail the entry it's about
+type Warn<'a> = Box<dyn Fn(&str) + Sync + 'a>;
+
+/// Walks a directory tree like `explore` does, for listing it from other
+/// programs. Every filter and column `explore` has options for is set up
+/// here, so the entries come out the same as in its listing
+///
+/// ```no_run
+/// for entry in explore::Explorer::new("src").max_depth(2).walk() {
+///     println!("{}", entry?.path.display());
+/// }
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+pub struct Explorer<'a> {
+    root: PathBuf,
+    min_depth: usize,
+    max_depth: usize,
+    hidden: bool,
+    sort_by_file_name: bool,
+    /// How many symlinks may be followed on the way to a directory that's
+    /// descended into, if symlinks are followed at all
+    follow_links: Option<usize>,
+    gitignore: bool,
+    path_filter: PathFilter,
+    name_filter: NameFilter,
+    types: Vec<FileKind>,
+    directories_only: bool,
+    min_name_length: Option<usize>,
+    max_name_length: Option<usize>,
+    name_length_bytes: bool,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    /// The range of sizes and whether its bounds are left out
+    size_range: Option<(SizeRange, bool)>,
+    newer_than: Option<SystemTime>,
+    older_than: Option<SystemTime>,
+    modified_since: Option<SystemTime>,
+    content: Option<ContentMatcher>,
+    broken_symlinks_only: bool,
+    /// The paths yielded instead of walking the root
+    paths: Option<Vec<PathBuf>>,
+    threads: usize,
+    unordered: bool,
+    count_children: bool,
+    min_children: Option<usize>,
+    count_lines: bool,
+    hash: Option<HashAlgorithm>,
+    directory_sizes: Option<&'a DirectorySizes>,
+    resolve_targets_size: bool,
+    newest: Option<&'a NewestModifications>,
+    newest_in_dir: bool,
+    stale_before: Option<SystemTime>,
+    predicates: Vec<Predicate<'a>>,
+    warn: Option<Warn<'a>>,
+}
+
+impl<'a> Explorer<'a> {
+    /// Explores everything below `root`, without the root itself or hidden
+    /// entries
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            min_depth: 1,
+            max_depth: usize::MAX,
+            hidden: false,
+            sort_by_file_name: false,
+            follow_links: None,
+            gitignore: false,
+            path_filter: PathFilter::default(),
+            name_filter: NameFilter::default(),
+            types: Vec::new(),
+            directories_only: false,
+            min_name_length: None,
+            max_name_length: None,
+            name_length_bytes: false,
+            min_size: None,
+            max_size: None,
+            size_range: None,
+            newer_than: None,
+            older_than: None,
+            modified_since: None,
+            content: None,
+            broken_symlinks_only: false,
+            paths: None,
+            threads: 1,
+            unordered: false,
+            count_children: false,
+            min_children: None,
+            count_lines: false,
+            hash: None,
+            directory_sizes: None,
+            resolve_targets_size: false,
+            newest: None,
+            newest_in_dir: false,
+            stale_before: None,
+            predicates: Vec::new(),
+            warn: None,
+        }
+    }
+
+    /// Only yield entries at least this deep. Depth 0 is the root itself
+    pub fn min_depth(mut self, depth: usize) -> Self {
+        self.min_depth = depth;
+        self
+    }
+
+    /// Don't yield or descend into entries deeper than this
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Whether to yield hidden entries and descend into hidden directories
+    pub fn hidden(mut self, hidden: bool) -> Self {
+        self.hidden = hidden;
+        self
+    }
+
+    /// Whether to yield the entries of each directory sorted by name, rather
+    /// than in the order the file system returns them
+    pub fn sort_by_file_name(mut self, sort: bool) -> Self {
+        self.sort_by_file_name = sort;
+        self
+    }
+
+    /// Follow symlinks, but only descend into directories reached through at
+    /// most `max_links` of them. Links pointing to nothing are yielded as the
+    /// links themselves, and links pointing back to one of their ancestors as
+    /// errors
+    pub fn follow_links(mut self, max_links: usize) -> Self {
+        self.follow_links = Some(max_links);
+        self
+    }
+
+    /// Whether to leave out what `.gitignore` and `.ignore` files or git's
+    /// global excludes ignore, along with the `.git` directory itself
+    pub fn gitignore(mut self, gitignore: bool) -> Self {
+        self.gitignore = gitignore;
+        self
+    }
+
+    /// Only yield the entries whose paths below the root pass `filter`
+    pub fn path_filter(mut self, filter: PathFilter) -> Self {
+        self.path_filter = filter;
+        self
+    }
+
+    /// Only yield the entries whose names pass `filter`
+    pub fn name_filter(mut self, filter: NameFilter) -> Self {
+        self.name_filter = filter;
+        self
+    }
+
+    /// Only yield entries of these kinds, or of any kind if there are none
+    pub fn types(mut self, types: Vec<FileKind>) -> Self {
+        self.types = types;
+        self
+    }
+
+    /// Whether to only yield directories, while still descending into them
+    pub fn directories_only(mut self, directories_only: bool) -> Self {
+        self.directories_only = directories_only;
+        self
+    }
+
+    /// Only yield entries whose names are within these lengths, counted in
+    /// bytes if `in_bytes` and in characters otherwise
+    pub fn name_length(mut self, min: Option<usize>, max: Option<usize>, in_bytes: bool) -> Self {
+        self.min_name_length = min;
+        self.max_name_length = max;
+        self.name_length_bytes = in_bytes;
+        self
+    }
+
+    /// Only yield files, symlinks and the like of at least `min` and at most
+    /// `max` bytes. Directories are yielded whatever their size
+    pub fn file_size(mut self, min: Option<u64>, max: Option<u64>) -> Self {
+        self.min_size = min;
+        self.max_size = max;
+        self
+    }
+
+    /// Only yield files, symlinks and the like whose size is within `range`,
+    /// or strictly between its bounds if `exclusive`. Directories are yielded
+    /// whatever their size
+    pub fn file_size_range(mut self, range: SizeRange, exclusive: bool) -> Self {
+        self.size_range = Some((range, exclusive));
+        self
+    }
+
+    /// Only yield files, symlinks and the like last modified after
+    /// `newer_than` and before `older_than`. Directories are yielded whenever
+    /// they were modified
+    pub fn modified(
+        mut self,
+        newer_than: Option<SystemTime>,
+        older_than: Option<SystemTime>,
+    ) -> Self {
+        self.newer_than = newer_than;
+        self.older_than = older_than;
+        self
+    }
+
+    /// Only yield files, symlinks and the like modified at `since` or later,
+    /// leaving out directories
+    pub fn modified_since(mut self, since: SystemTime) -> Self {
+        self.modified_since = Some(since);
+        self
+    }
+
+    /// Only yield files whose contents `matcher` matches
+    pub fn content(mut self, matcher: ContentMatcher) -> Self {
+        self.content = Some(matcher);
+        self
+    }
+
+    /// Whether to only yield symlinks pointing to something that doesn't exist
+    pub fn broken_symlinks_only(mut self, broken_only: bool) -> Self {
+        self.broken_symlinks_only = broken_only;
+        self
+    }
+
+    /// Yield the entries at `paths` instead of walking the root, each on its
+    /// own and without descending into directories. They still go through the
+    /// filters on names, kinds, sizes and the like
+    pub fn paths(mut self, paths: Vec<PathBuf>) -> Self {
+        self.paths = Some(paths);
+        self
+    }
+
+    /// Walk the tree, read the metadata of entries, count their lines or
+    /// children and hash them on this many threads. With more than one, the
+    /// whole tree is walked before the first entry is yielded, each directory
+    /// still before its contents but sorted by name
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// Whether to yield entries in the order the threads found them instead,
+    /// which changes from run to run and doesn't keep a directory's contents
+    /// together, but saves sorting them. Only applies with several
+    /// [`Explorer::threads`]
+    pub fn unordered(mut self, unordered: bool) -> Self {
+        self.unordered = unordered;
+        self
+    }
+
+    /// Whether to count the entries directories directly contain
+    pub fn count_children(mut self, count: bool) -> Self {
+        self.count_children = count;
+        self
+    }
+
+    /// Leave out directories directly containing fewer than `min` entries,
+    /// counting them as with [`Explorer::count_children`]
+    pub fn min_children(mut self, min: usize) -> Self {
+        self.min_children = Some(min);
+        self
+    }
+
+    /// Whether to count the lines of text files
+    pub fn count_lines(mut self, count: bool) -> Self {
+        self.count_lines = count;
+        self
+    }
+
+    /// Hash the contents of files with `algorithm`, if given
+    pub fn hash(mut self, algorithm: Option<HashAlgorithm>) -> Self {
+        self.hash = algorithm;
+        self
+    }
+
+    /// Give directories the total size of their contents from `sizes`
+    pub fn directory_sizes(mut self, sizes: &'a DirectorySizes) -> Self {
+        self.directory_sizes = Some(sizes);
+        self
+    }
+
+    /// Whether to give symlinks the size of what they point to. Links that
+    /// can't be resolved keep their own size, with a warning
+    pub fn resolve_targets_size(mut self, resolve: bool) -> Self {
+        self.resolve_targets_size = resolve;
+        self
+    }
+
+    /// Give directories the file within them modified last from `newest`,
+    /// leaving out those without any files
+    pub fn newest_in_dir(mut self, newest: &'a NewestModifications) -> Self {
+        self.newest = Some(newest);
+        self.newest_in_dir = true;
+        self
+    }
+
+    /// Only yield the directories in which nothing was modified since
+    /// `before`, according to `newest`
+    pub fn stale(mut self, newest: &'a NewestModifications, before: SystemTime) -> Self {
+        self.newest = Some(newest);
+        self.stale_before = Some(before);
+        self
+    }
+
+    /// Also leave out the entries `predicate` rejects, along with everything
+    /// within them. It sees every entry the walk finds before any of the other
+    /// filters, but not the paths given to [`Explorer::paths`]. With several
+    /// threads it's called from all of them
+    pub fn filter_entry(mut self, predicate: impl Fn(&Candidate) -> bool + Sync + 'a) -> Self {
+        self.predicates.push(Box::new(predicate));
+        self
+    }
+
+    /// Report what goes wrong without failing the entry, like targets of
+    /// symlinks that can't be resolved, to `warn` rather than ignoring it
+    pub fn on_warning(mut self, warn: impl Fn(&str) + Sync + 'a) -> Self {
+        self.warn = Some(Box::new(warn));
+        self
+    }
+
+    /// The entries below the root, each directory before its contents. Entries
+    /// that can't be read are yielded as errors without ending the walk
+    pub fn walk(self) -> impl Iterator<Item = anyhow::Result<Entry>> + 'a {
+        let Self {
+            root,
+            min_depth,
+            max_depth,
+            hidden,
+            sort_by_file_name,
+            follow_links,
+            gitignore,
+            path_filter,
+            name_filter,
+            types,
+            directories_only,
+            min_name_length,
+            max_name_length,
+            name_length_bytes,
+            min_size,
+            max_size,
+            size_range,
+            newer_than,
+            older_than,
+            modified_since,
+            content,
+            broken_symlinks_only,
+            paths,
+            threads,
+            unordered,
+            count_children,
+            min_children,
+            count_lines,
+            hash,
+            directory_sizes,
+            resolve_targets_size,
+            newest,
+            newest_in_dir,
+            stale_before,
+            predicates,
+            warn,
+        } = self;
+        let root: Arc<Path> = root.into();
+        let path_filter = Arc::new(path_filter);
+        let listing_paths = paths.is_some();
+
+        let absolute_root = resolved_path(&root);
+        let ignore_rules = gitignore.then(|| Mutex::new(IgnoreRules::default()));
+        let keep = {
+            let (root, path_filter) = (root.clone(), path_filter.clone());
+            move |candidate: &Candidate| {
+                if !predicates.iter().all(|predicate| predicate(candidate)) {
+                    return false;
+                }
+                let is_hidden = || {
+                    candidate.depth > 0
+                        && is_hidden_entry(candidate.file_name(), candidate.metadata)
+                };
+                let is_excluded = || {
+                    candidate.depth > 0
+                        && path_filter.is_excluded(
+                            candidate.path.strip_prefix(&root).unwrap_or(candidate.path),
+                            candidate.is_dir,
+                        )
+                };
+                let is_ignored = || {
+                    candidate.depth > 0
+                        && ignore_rules.as_ref().is_some_and(|rules| {
+                            // SAFETY: Nothing panics while holding the lock
+                            rules.lock().unwrap().is_ignored(
+                                &absolute_entry_path(candidate.path, &root, &absolute_root),
+                                candidate.is_dir,
+                            )
+                        })
+                };
+                (hidden || !is_hidden()) && !is_excluded() && !is_ignored()
+            }
+        };
+
+        let walk: Box<dyn Iterator<Item = Result<Walked, WalkError>> + 'a> = match paths {
+            Some(paths) => {
+                let follow = follow_links.is_some();
+                Box::new(paths.into_iter().map(move |path| Walked::at(&path, follow)))
+            }
+            None if threads > 1 => {
+                let root = root.clone();
+                let mut found = None;
+                Box::new(std::iter::from_fn(move || {
+                    found
+                        .get_or_insert_with(|| {
+                            let mut found = parallel::walk_tree(
+                                &root,
+                                threads,
+                                min_depth,
+                                max_depth,
+                                follow_links,
+                                &keep,
+                            );
+                            if !unordered {
+                                // Paths compare by their parts, which puts
+                                // directories before their contents
+                                found.sort_by(|a, b| walk_order(a).cmp(&walk_order(b)));
+                            }
+                            found.into_iter()
+                        })
+                        .next()
+                }))
+            }
+            None => {
+                let mut walk = WalkDir::new(&root)
+                    .min_depth(min_depth)
+                    .max_depth(max_depth)
+                    .follow_links(follow_links.is_some());
+                if sort_by_file_name {
+                    walk = walk.sort_by_file_name();
+                }
+                let walk = walk.into_iter().filter_entry(move |entry| {
+                    stats::record_visit();
+                    let metadata = || entry.metadata().ok();
+                    keep(&Candidate::new(
+                        entry.path(),
+                        entry.depth(),
+                        entry.file_type().is_dir(),
+                        &metadata,
+                    ))
+                });
+                Box::new(FollowDepth::new(walk, follow_links.unwrap_or(0)))
+            }
+        };
+
+        let relative = {
+            let root = root.clone();
+            move |path: &Path| -> PathBuf { path.strip_prefix(&root).unwrap_or(path).to_path_buf() }
+        };
+        let walk = walk
+            .filter({
+                let (path_filter, relative) = (path_filter.clone(), relative.clone());
+                move |entry| match entry {
+                    // The walk fails on links pointing to nothing before
+                    // they're filtered, so they're checked here instead
+                    Ok(link @ Walked::BrokenLink { .. }) if !listing_paths => {
+                        (hidden
+                            || !is_hidden_entry(link.file_name(), || {
+                                link.path().symlink_metadata().ok()
+                            }))
+                            && !path_filter.is_excluded(&relative(link.path()), false)
+                    }
+                    _ => true,
+                }
+            })
+            .filter(move |entry| match entry {
+                Ok(entry) if broken_symlinks_only => {
+                    entry.path_is_symlink()
+                        && stats::stat_call(|| std::fs::metadata(entry.path())).is_err()
+                }
+                _ => true,
+            })
+            .filter(move |entry| match entry {
+                Ok(entry) if !types.is_empty() => {
+                    FileKind::of(entry.file_type()).is_some_and(|kind| types.contains(&kind))
+                }
+                _ => true,
+            })
+            .filter(move |entry| match entry {
+                Ok(entry) => !directories_only || entry.file_type().is_dir(),
+                Err(_) => true,
+            })
+            .filter(move |entry| match entry {
+                Ok(entry) => path_filter.is_included(&relative(entry.path())),
+                Err(_) => true,
+            })
+            .filter(move |entry| match entry {
+                Ok(entry) => name_filter.matches(
+                    &entry.file_name().to_string_lossy(),
+                    entry.file_type().is_dir(),
+                ),
+                Err(_) => true,
+            })
+            .filter(move |entry| match entry {
+                Ok(entry) => {
+                    let length = filter::name_length(entry.file_name(), name_length_bytes);
+                    min_name_length.is_none_or(|min| length >= min)
+                        && max_name_length.is_none_or(|max| length <= max)
+                }
+                Err(_) => true,
+            });
+
+        let describe = move |walked: Walked| -> anyhow::Result<Entry> {
+            let mut entry = walked.into_entry()?;
+            if let Some(size) = directory_sizes.and_then(|sizes| sizes.get(&entry.path)) {
+                entry.size = size;
+            }
+            if resolve_targets_size && entry.metadata.file_type().is_symlink() {
+                match stats::stat_call(|| std::fs::metadata(&entry.path)) {
+                    Ok(target) => entry.size = target.len(),
+                    Err(error) => {
+                        if let Some(warn) = &warn {
+                            warn(&format!(
+                                "Could not resolve the target of {}: {error}",
+                                entry.path.display()
+                            ));
+                        }
+                    }
+                }
+            }
+            if (count_children || min_children.is_some()) && entry.metadata.is_dir() {
+                entry.count_children()?;
+            }
+            if count_lines && entry.metadata.is_file() {
+                entry.count_lines()?;
+            }
+            if let Some(algorithm) = hash.filter(|_| entry.metadata.is_file()) {
+                entry.hash(algorithm)?;
+            }
+            if newest_in_dir {
+                entry.newest_file = newest
+                    .and_then(|newest| newest.file(&entry.path))
+                    .map(|(file, modified)| (file.to_path_buf(), modified));
+            }
+            Ok(entry)
+        };
+        // Reading contents is slow enough to be worth spreading over the threads
+        let matches_content = move |walked: &Walked| {
+            content.as_ref().is_none_or(|matcher| {
+                walked.file_type().is_file() && matcher.matches(walked.path())
+            })
+        };
+        ParallelMap::new(walk, threads, move |entry| match entry {
+            Ok(walked) if !matches_content(&walked) => None,
+            entry => Some(
+                entry
+                    .context("Error getting file entry")
+                    .and_then(&describe),
+            ),
+        })
+        .flatten()
+        .filter(move |entry| match entry {
+            // Directories without any files have nothing to show
+            Ok(entry) => !newest_in_dir || entry.newest_file.is_some(),
+            Err(_) => true,
+        })
+        .filter(move |entry| match entry {
+            Ok(entry) if !entry.metadata.is_dir() => {
+                min_size.is_none_or(|min| entry.size >= min)
+                    && max_size.is_none_or(|max| entry.size <= max)
+                    && size_range
+                        .is_none_or(|(range, exclusive)| range.contains(entry.size, exclusive))
+            }
+            _ => true,
+        })
+        .filter(move |entry| match (entry, min_children) {
+            (Ok(entry), Some(min)) => entry.children.is_none_or(|count| count >= min),
+            _ => true,
+        })
+        .filter(move |entry| match entry {
+            Ok(entry) if !entry.metadata.is_dir() => {
+                let modified = entry.metadata.modified().ok();
+                newer_than.is_none_or(|newer| modified.is_some_and(|modified| modified > newer))
+                    && older_than
+                        .is_none_or(|older| modified.is_some_and(|modified| modified < older))
+            }
+            _ => true,
+        })
+        .filter(move |entry| match (entry, modified_since) {
+            (Ok(entry), Some(since)) => {
+                !entry.metadata.is_dir()
+                    && entry
+                        .metadata
+                        .modified()
+                        .is_ok_and(|modified| modified >= since)
+            }
+            _ => true,
+        })
+        .filter(move |entry| match (entry, newest, stale_before) {
+            (Ok(entry), Some(newest), Some(before)) => newest
+                .modified(&entry.path)
+                .is_some_and(|modified| modified < before),
+            _ => true,
+        })
+    }
+}
+
+/// Where the walk on several threads puts what it found, or an error with no
+/// path, when it's put in order
+fn walk_order(walked: &Result<Walked, WalkError>) -> (bool, Option<&Path>) {
+    let path = match walked {
+        Ok(walked) => Some(walked.path()),
+        Err(error) => error.path(),
+    };
+    (path.is_none(), path)
+}
+
+/// An entry the walk found, for [`Explorer::filter_entry`] to decide on
+/// before it goes through the other filters
+pub struct Candidate<'e> {
+    pub path: &'e Path,
+    /// How far below the root it is, which is 0 for the root itself
+    pub depth: usize,
+    /// Whether it's a directory, or a symlink to one that's followed
+    pub is_dir: bool,
+    metadata: &'e dyn Fn() -> Option<Metadata>,
+}
+
+impl<'e> Candidate<'e> {
+    pub(crate) fn new(
+        path: &'e Path,
+        depth: usize,
+        is_dir: bool,
+        metadata: &'e dyn Fn() -> Option<Metadata>,
+    ) -> Self {
+        Self {
+            path,
+            depth,
+            is_dir,
+            metadata,
+        }
+    }
+
+    pub fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+
+    /// Its metadata, read when it's asked for, if it could be
+    pub fn metadata(&self) -> Option<Metadata> {
+        (self.metadata)()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// The paths below `root` that `explorer` yields, in walk order
+    fn walked(explorer: Explorer, root: &std::path::Path) -> Vec<String> {
+        explorer
+            .sort_by_file_name(true)
+            .walk()
+            .map(|entry| {
+                let path = entry.unwrap().path;
+                path.strip_prefix(root).unwrap().display().to_string()
+            })
+            .collect()
+    }
+
+    /// A root that's hidden itself, like `.` is, with a directory in it
+    fn hidden_root() -> (tempfile::TempDir, PathBuf) {
+        let directory = tempfile::tempdir().unwrap();
+        let root = directory.path().join(".root");
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        fs::write(root.join(".hidden"), "").unwrap();
+        (directory, root)
+    }
+
+    #[test]
+    fn min_depth_0_includes_a_hidden_root() {
+        let (_directory, root) = hidden_root();
+        let explorer = Explorer::new(&root).min_depth(0);
+        assert_eq!(walked(explorer, &root), ["", "a", "a/b"]);
+    }
+
+    #[test]
+    fn min_depth_0_and_max_depth_0_is_only_the_root() {
+        let (_directory, root) = hidden_root();
+        let explorer = Explorer::new(&root).min_depth(0).max_depth(0);
+        assert_eq!(walked(explorer, &root), [""]);
+    }
+
+    #[test]
+    fn min_depth_1_is_everything_below_the_root() {
+        let (_directory, root) = hidden_root();
+        let explorer = Explorer::new(&root);
+        assert_eq!(walked(explorer, &root), ["a", "a/b"]);
+    }
+
+    #[test]
+    fn min_depth_1_and_max_depth_1_is_only_the_root_s_contents() {
+        let (_directory, root) = hidden_root();
+        let explorer = Explorer::new(&root).max_depth(1);
+        assert_eq!(walked(explorer, &root), ["a"]);
+    }
+
+    #[test]
+    fn several_threads_walk_in_the_same_order() {
+        let (_directory, root) = hidden_root();
+        fs::create_dir_all(root.join("a-c/d")).unwrap();
+        fs::write(root.join("a/b/file"), "").unwrap();
+        let explorer = || Explorer::new(&root).min_depth(0).hidden(true);
+        assert_eq!(
+            walked(explorer().threads(4), &root),
+            walked(explorer(), &root)
+        );
+    }
+
+    #[test]
+    fn unordered_finds_the_same_entries() {
+        let (_directory, root) = hidden_root();
+        let mut found = walked(Explorer::new(&root).threads(4).unordered(true), &root);
+        found.sort();
+        assert_eq!(found, ["a", "a/b"]);
+    }
+}
@@ -0,0 +1,259 @@
+use std::{
+    ffi::OsStr,
+    path::Path,
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+use bytesize::ByteSize;
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use regex::{Regex, RegexBuilder};
+
+/// Filters on entry names from `--glob`, `--regex`, `--ext` and
+/// `--exclude-ext`. An entry has to match all of those given to be listed
+#[derive(Default)]
+pub struct NameFilter {
+    globs: Option<GlobSet>,
+    regex: Option<Regex>,
+    extensions: Vec<String>,
+    /// Always lowercase, as excluded extensions are matched ignoring case
+    excluded_extensions: Vec<String>,
+    ignore_case: bool,
+}
+
+impl NameFilter {
+    pub fn new(
+        globs: &[String],
+        regex: Option<&str>,
+        extensions: &[String],
+        excluded_extensions: &[String],
+        ignore_case: bool,
+    ) -> anyhow::Result<Self> {
+        let globs = glob_set(globs, ignore_case)?;
+
+        let regex = regex
+            .map(|regex| {
+                RegexBuilder::new(regex)
+                    .case_insensitive(ignore_case)
+                    .build()
+                    .context(format!("Invalid regex {regex}"))
+            })
+            .transpose()?;
+
+        let extensions = extensions
+            .iter()
+            .map(|extension| normalize_extension(extension, ignore_case))
+            .collect();
+        let excluded_extensions = excluded_extensions
+            .iter()
+            .map(|extension| normalize_extension(extension, true))
+            .collect();
+
+        Ok(Self {
+            globs,
+            regex,
+            extensions,
+            excluded_extensions,
+            ignore_case,
+        })
+    }
+
+    /// Whether an entry named `name` passes the filters. Directories are
+    /// never excluded by their extension, but with `--ext` they're only listed
+    /// if their name has one of the extensions too. Either way the walk still
+    /// descends into them
+    pub fn matches(&self, name: &str, is_dir: bool) -> bool {
+        let matches_glob = self.globs.as_ref().is_none_or(|globs| globs.is_match(name));
+        let matches_regex = self.regex.as_ref().is_none_or(|regex| regex.is_match(name));
+        let matches_extension = self.extensions.is_empty()
+            || extension(name, self.ignore_case)
+                .is_some_and(|extension| self.extensions.contains(&extension));
+        let is_excluded = !is_dir
+            && !self.excluded_extensions.is_empty()
+            && extension(name, true)
+                .is_some_and(|extension| self.excluded_extensions.contains(&extension));
+
+        matches_glob && matches_regex && matches_extension && !is_excluded
+    }
+}
+
+/// Combines `globs` into one set matching any of them, or `None` if there are
+/// none
+fn glob_set(globs: &[String], ignore_case: bool) -> anyhow::Result<Option<GlobSet>> {
+    if globs.is_empty() {
+        return Ok(None);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        builder.add(
+            GlobBuilder::new(glob)
+                .case_insensitive(ignore_case)
+                .build()
+                .context(format!("Invalid glob {glob}"))?,
+        );
+    }
+    Ok(Some(builder.build().context("Failed combining globs")?))
+}
+
+/// Filters on paths relative to the root from `--include` and `--exclude`
+#[derive(Default)]
+pub struct PathFilter {
+    include: Option<GlobSet>,
+    exclude: Option<GlobSet>,
+}
+
+impl PathFilter {
+    pub fn new(include: &[String], exclude: &[String], ignore_case: bool) -> anyhow::Result<Self> {
+        Ok(Self {
+            include: glob_set(include, ignore_case)?,
+            exclude: glob_set(exclude, ignore_case)?,
+        })
+    }
+
+    /// Whether the entry at `relative` should be left out along with
+    /// everything inside it. Directories are also left out if the exclusions
+    /// match whatever they contain, so `target/**` skips `target` as a whole
+    pub fn is_excluded(&self, relative: &Path, is_dir: bool) -> bool {
+        let Some(exclude) = &self.exclude else {
+            return false;
+        };
+        // Stands in for any entry within the directory
+        let child = || relative.join("\u{0}");
+        exclude.is_match(relative) || (is_dir && exclude.is_match(child()))
+    }
+
+    /// Whether the entry at `relative` passes the inclusions. Directories
+    /// only do if they match themselves, but the walk still descends into
+    /// them so the files within them can
+    pub fn is_included(&self, relative: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(relative))
+    }
+}
+
+/// Normalizes an extension given by the user, so `.RS`, `rs` and `RS` are
+/// all the same when ignoring case
+pub fn normalize_extension(extension: &str, ignore_case: bool) -> String {
+    let extension = extension.trim_start_matches('.');
+    if ignore_case {
+        extension.to_lowercase()
+    } else {
+        extension.to_owned()
+    }
+}
+
+/// The normalized extension of the file `name`, if it has one
+pub fn extension(name: &str, ignore_case: bool) -> Option<String> {
+    let (stem, extension) = name.rsplit_once('.')?;
+    // Dotfiles like `.gitignore` have no extension
+    if stem.is_empty() {
+        return None;
+    }
+    Some(normalize_extension(extension, ignore_case))
+}
+
+/// The length of the name `name`, in UTF-8 bytes if `bytes` and in characters
+/// otherwise
+pub fn name_length(name: &OsStr, bytes: bool) -> usize {
+    if bytes {
+        name.as_encoded_bytes().len()
+    } else {
+        name.to_string_lossy().chars().count()
+    }
+}
+
+/// A range of sizes like `1MB..100MB`, given to `--between-size`
+#[derive(Clone, Copy, Debug)]
+pub struct SizeRange {
+    pub low: ByteSize,
+    pub high: ByteSize,
+}
+
+impl SizeRange {
+    /// Whether `size` is within the range, including the bounds unless
+    /// `exclusive`
+    pub fn contains(&self, size: u64, exclusive: bool) -> bool {
+        let (low, high) = (self.low.as_u64(), self.high.as_u64());
+        if exclusive {
+            low < size && size < high
+        } else {
+            low <= size && size <= high
+        }
+    }
+}
+
+impl FromStr for SizeRange {
+    type Err = String;
+
+    fn from_str(range: &str) -> Result<Self, Self::Err> {
+        let (low, high) = range
+            .split_once("..")
+            .ok_or_else(|| format!("expected a range like 1MB..100MB, not {range}"))?;
+        let low: ByteSize = low.trim().parse()?;
+        let high: ByteSize = high.trim().parse()?;
+        if low > high {
+            return Err(format!("{low} is larger than {high}"));
+        }
+        Ok(Self { low, high })
+    }
+}
+
+/// A point in time given to `--newer-than` or `--older-than`, either as a
+/// date like `2024-01-01` (midnight UTC) or `2024-01-01 12:00:00`, or as a
+/// duration like `7d` before now
+#[derive(Clone, Copy, Debug)]
+pub struct TimePoint(pub SystemTime);
+
+impl FromStr for TimePoint {
+    type Err = String;
+
+    fn from_str(time: &str) -> Result<Self, Self::Err> {
+        if let Ok(duration) = humantime::parse_duration(time) {
+            return Ok(Self(
+                SystemTime::now()
+                    .checked_sub(duration)
+                    .unwrap_or(UNIX_EPOCH),
+            ));
+        }
+        if let Ok(date) = chrono::NaiveDate::parse_from_str(time, "%Y-%m-%d") {
+            // SAFETY: Midnight exists on every day
+            let midnight = date.and_hms_opt(0, 0, 0).unwrap().and_utc();
+            return Ok(Self(midnight.into()));
+        }
+        humantime::parse_rfc3339_weak(time).map(Self).map_err(|_| {
+            format!("expected a date like 2024-01-01 or a duration like 7d, not {time}")
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn excluding(extensions: &[&str]) -> NameFilter {
+        let extensions: Vec<String> = extensions
+            .iter()
+            .map(|extension| extension.to_string())
+            .collect();
+        NameFilter::new(&[], None, &[], &extensions, false).unwrap()
+    }
+
+    #[test]
+    fn exclude_ext_leaves_directories_with_the_extension_in() {
+        assert!(excluding(&["d"]).matches("conf.d", true));
+    }
+
+    #[test]
+    fn exclude_ext_leaves_files_with_the_extension_out() {
+        assert!(!excluding(&["d"]).matches("conf.d", false));
+        assert!(!excluding(&[".LOG"]).matches("app.log", false));
+    }
+
+    #[test]
+    fn exclude_ext_leaves_other_files_in() {
+        assert!(excluding(&["d"]).matches("main.rs", false));
+    }
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fmt,
+    fs::FileType,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+use walkdir::{DirEntry, FilterEntry, IntoIter, WalkDir};
+
+use crate::entry::Entry;
+
+/// A walk following symlinks that only descends into directories reached
+/// through at most `max_links` symlinks along the way. Directories beyond that
+/// are still listed, just not descended into
+pub struct FollowDepth<P> {
+    walk: FilterEntry<IntoIter, P>,
+    max_links: usize,
+    /// How many symlinks were followed to reach directories reached through
+    /// any at all
+    links: HashMap<PathBuf, usize>,
+}
+
+/// An entry of a walk following symlinks, where the symlinks pointing to
+/// nothing that the walk fails on are listed as the links themselves
+pub enum Walked {
+    Entry(DirEntry),
+    /// Found by the walk on several threads
+    Parallel(ignore::DirEntry),
+    BrokenLink {
+        path: PathBuf,
+        depth: usize,
+        file_type: FileType,
+    },
+}
+
+impl Walked {
+    pub fn path(&self) -> &Path {
+        match self {
+            Self::Entry(entry) => entry.path(),
+            Self::Parallel(entry) => entry.path(),
+            Self::BrokenLink { path, .. } => path,
+        }
+    }
+
+    pub fn file_name(&self) -> &OsStr {
+        match self {
+            Self::Entry(entry) => entry.file_name(),
+            Self::Parallel(entry) => entry.file_name(),
+            Self::BrokenLink { path, .. } => path.file_name().unwrap_or(path.as_os_str()),
+        }
+    }
+
+    /// The type of what's been followed to, or of the link itself if it's
+    /// broken
+    pub fn file_type(&self) -> FileType {
+        match self {
+            Self::Entry(entry) => entry.file_type(),
+            // SAFETY: Only entries for stdin have no type, which the walk
+            // never yields as it's only given the root's path
+            Self::Parallel(entry) => entry.file_type().unwrap(),
+            Self::BrokenLink { file_type, .. } => *file_type,
+        }
+    }
+
+    pub fn path_is_symlink(&self) -> bool {
+        match self {
+            Self::Entry(entry) => entry.path_is_symlink(),
+            Self::Parallel(entry) => entry.path_is_symlink(),
+            Self::BrokenLink { .. } => true,
+        }
+    }
+
+    pub fn into_entry(self) -> anyhow::Result<Entry> {
+        match self {
+            Self::Entry(entry) => Entry::new(entry),
+            Self::Parallel(entry) => Entry::found(
+                entry.path().to_path_buf(),
+                entry.depth(),
+                entry.path_is_symlink(),
+                || entry.metadata(),
+            ),
+            Self::BrokenLink { path, depth, .. } => {
+                let mut entry = Entry::link_at(&path)?;
+                entry.depth = depth;
+                Ok(entry)
+            }
+        }
+    }
+
+    /// The entry at `path` on its own, as if it was the root of a walk, with
+    /// a symlink being followed only if `follow`
+    pub fn at(path: &Path, follow: bool) -> Result<Self, WalkError> {
+        // SAFETY: A walk always yields its root, even if only as an error
+        let entry = WalkDir::new(path)
+            .follow_links(follow)
+            .follow_root_links(follow)
+            .max_depth(0)
+            .into_iter()
+            .next()
+            .unwrap();
+        entry
+            .map(Self::Entry)
+            .map_err(WalkError::from)
+            .or_else(|error| Self::broken_link(&error).ok_or(error))
+    }
+
+    /// The symlink the walk failed on with `error` because what it points to
+    /// doesn't exist, if that's what happened
+    pub(crate) fn broken_link(error: &WalkError) -> Option<Self> {
+        let path = error.path()?;
+        if error.io_error()?.kind() != ErrorKind::NotFound {
+            return None;
+        }
+        let metadata = path
+            .symlink_metadata()
+            .ok()
+            .filter(|metadata| metadata.is_symlink())?;
+        Some(Self::BrokenLink {
+            path: path.to_path_buf(),
+            depth: error.depth(),
+            file_type: metadata.file_type(),
+        })
+    }
+}
+
+/// What went wrong walking the tree, either on one thread or on several
+#[derive(Debug)]
+pub struct WalkError {
+    path: Option<PathBuf>,
+    depth: usize,
+    kind: WalkErrorKind,
+}
+
+#[derive(Debug)]
+enum WalkErrorKind {
+    Io(io::Error),
+    /// A followed symlink pointing to a directory it's within
+    Loop {
+        ancestor: PathBuf,
+    },
+    Other(String),
+}
+
+impl WalkError {
+    /// The path of what couldn't be read, or of the link pointing back to its
+    /// ancestor
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match &self.kind {
+            WalkErrorKind::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The directory a followed symlink points back to, if that's what
+    /// went wrong
+    pub fn loop_ancestor(&self) -> Option<&Path> {
+        match &self.kind {
+            WalkErrorKind::Loop { ancestor } => Some(ancestor),
+            _ => None,
+        }
+    }
+}
+
+impl From<walkdir::Error> for WalkError {
+    fn from(error: walkdir::Error) -> Self {
+        let path = error.path().map(Path::to_path_buf);
+        let depth = error.depth();
+        let kind = match error.loop_ancestor() {
+            Some(ancestor) => WalkErrorKind::Loop {
+                ancestor: ancestor.to_path_buf(),
+            },
+            None => match error.into_io_error() {
+                Some(error) => WalkErrorKind::Io(error),
+                // Walk errors are either about I/O or loops
+                None => WalkErrorKind::Other("unknown walk error".into()),
+            },
+        };
+        Self { path, depth, kind }
+    }
+}
+
+impl From<ignore::Error> for WalkError {
+    fn from(error: ignore::Error) -> Self {
+        match error {
+            ignore::Error::WithPath { path, err } => Self {
+                path: Some(path),
+                ..Self::from(*err)
+            },
+            ignore::Error::WithDepth { depth, err } => Self {
+                depth,
+                ..Self::from(*err)
+            },
+            ignore::Error::Loop { ancestor, child } => Self {
+                path: Some(child),
+                depth: 0,
+                kind: WalkErrorKind::Loop { ancestor },
+            },
+            ignore::Error::Io(error) => Self {
+                path: None,
+                depth: 0,
+                kind: WalkErrorKind::Io(error),
+            },
+            error => Self {
+                path: None,
+                depth: 0,
+                kind: WalkErrorKind::Other(error.to_string()),
+            },
+        }
+    }
+}
+
+/// Reads like the errors of `walkdir`, which the walk on one thread fails with
+impl fmt::Display for WalkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (&self.kind, &self.path) {
+            (WalkErrorKind::Io(error), Some(path)) => {
+                write!(f, "IO error for operation on {}: {error}", path.display())
+            }
+            (WalkErrorKind::Io(error), None) => error.fmt(f),
+            (WalkErrorKind::Loop { ancestor }, path) => write!(
+                f,
+                "File system loop found: {} points to an ancestor {}",
+                path.as_deref().unwrap_or(Path::new("")).display(),
+                ancestor.display()
+            ),
+            (WalkErrorKind::Other(error), _) => f.write_str(error),
+        }
+    }
+}
+
+impl std::error::Error for WalkError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.io_error().map(|error| error as _)
+    }
+}
+
+impl<P> FollowDepth<P> {
+    pub fn new(walk: FilterEntry<IntoIter, P>, max_links: usize) -> Self {
+        Self {
+            walk,
+            max_links,
+            links: HashMap::new(),
+        }
+    }
+}
+
+impl<P: FnMut(&DirEntry) -> bool> Iterator for FollowDepth<P> {
+    type Item = Result<Walked, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = match self.walk.next()? {
+            Ok(entry) => entry,
+            // Broken links have nothing to descend into, so there's no need to
+            // count them
+            Err(error) => {
+                let error = WalkError::from(error);
+                return Some(Walked::broken_link(&error).ok_or(error));
+            }
+        };
+
+        let parent_links = entry
+            .path()
+            .parent()
+            .and_then(|parent| self.links.get(parent))
+            .copied()
+            .unwrap_or(0);
+        let links = parent_links + usize::from(entry.path_is_symlink());
+
+        if entry.file_type().is_dir() && links > 0 {
+            if links > self.max_links {
+                // Skips the contents of the directory we're about to yield
+                self.walk.skip_current_dir();
+            } else {
+                self.links.insert(entry.path().to_path_buf(), links);
+            }
+        }
+
+        Some(Ok(Walked::Entry(entry)))
+    }
+}
@@ -0,0 +1,153 @@
+use std::{fmt::Display, time::SystemTime};
+
+use bytesize::ByteSize;
+use clap::ValueEnum;
+use colored::Colorize;
+
+use crate::{
+    entry::Entry,
+    icons::IconSet,
+    theme::{Style, Theme},
+};
+
+/// Repeated in front of names to show how deep they are
+pub const INDENTATION_SEQUENCE: &str = "⤷ ";
+
+/// How wide the size column is by default, which fits e.g. `1023.9 KB`
+pub const SIZE_WIDTH: usize = 9;
+
+/// How wide date columns are by default, which fits e.g.
+/// `Tue, 28 Nov 2023 19:04:04`
+pub const DATE_WIDTH: usize = 25;
+
+pub struct FormatEntry<'walk_dir_loop> {
+    pub entry: &'walk_dir_loop Entry,
+    /// Show the whole path without indentation rather than the indented name
+    pub show_path: bool,
+    /// Indent names by their depth
+    pub indent: bool,
+    /// Make the name stand out, for `--emphasize`
+    pub emphasized: bool,
+    /// Show an icon of the kind of entry in front of the name
+    pub icons: Option<IconSet>,
+    pub theme: &'walk_dir_loop Theme,
+}
+
+impl<'walk_dir_loop> Display for FormatEntry<'walk_dir_loop> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let path = &self.entry.path;
+        let name = if self.show_path {
+            path.to_string_lossy()
+        } else {
+            self.entry.name.as_str().into()
+        };
+        let file_type = self.entry.metadata.file_type();
+        let style = if file_type.is_file() {
+            self.theme.file_style(&self.entry.name)
+        } else if file_type.is_dir() {
+            self.theme.dir
+        } else {
+            self.theme.symlink
+        };
+        let mut formatted_entry = style.paint(&name);
+        if self.emphasized {
+            formatted_entry = formatted_entry.bold().reversed();
+        }
+
+        let depth = if self.show_path || !self.indent {
+            0
+        } else {
+            self.entry.depth
+        };
+        let indent = match &self.entry.tree_prefix {
+            Some(prefix) if !self.show_path => self.theme.background.paint(prefix),
+            _ => self
+                .theme
+                .background
+                .paint(&INDENTATION_SEQUENCE.repeat(depth.saturating_sub(1))),
+        };
+
+        let icon = match self.icons {
+            Some(icons) => {
+                let icon = icons.icon(self.entry.metadata.file_type(), &self.entry.name);
+                format!("{} ", style.paint(icon))
+            }
+            None => "".into(),
+        };
+
+        f.write_fmt(format_args!("{indent}{icon}{formatted_entry}"))
+    }
+}
+
+/// How to show the size of a directory itself, which says nothing about how
+/// much it contains
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum DirSize {
+    /// The size the filesystem reports, often 4 KB
+    Inode,
+    /// Always 0 B
+    Zero,
+    /// A dash instead of a size
+    #[default]
+    Dash,
+}
+
+pub struct FormatSize<'theme> {
+    pub size: u64,
+    /// How to show the size if it's a directory's own, rather than the size
+    /// of its contents
+    pub directory: Option<DirSize>,
+    /// How many characters to right-align the size in
+    pub width: usize,
+    pub theme: &'theme Theme,
+}
+
+impl FormatSize<'_> {
+    /// The size right-aligned in `width`, without its style
+    pub fn text(&self) -> String {
+        let size = match self.directory {
+            Some(DirSize::Dash) => return format!("{:>width$}", "—", width = self.width),
+            Some(DirSize::Zero) => 0,
+            Some(DirSize::Inode) | None => self.size,
+        };
+        format!("{:>width$}", ByteSize(size), width = self.width)
+    }
+
+    /// The style of the size, or of the background for a dash
+    pub fn style(&self) -> Style {
+        match self.directory {
+            Some(DirSize::Dash) => self.theme.background,
+            _ => self.theme.size,
+        }
+    }
+}
+
+impl Display for FormatSize<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.style().paint(&self.text()))
+    }
+}
+
+/// Formats `time` like `Tue, 28 Nov 2023 19:04:04`, in UTC
+pub fn format_date(time: SystemTime) -> String {
+    let date = chrono::DateTime::<chrono::Utc>::from(time);
+    date.to_rfc2822()
+        // SAFETY: We know it is in UTC so the stripping always works, probably 🤠
+        .strip_suffix(" +0000")
+        .unwrap()
+        .to_owned()
+}
+
+pub struct FormatModifiedAt<'theme>(pub SystemTime, pub &'theme Theme);
+
+impl Display for FormatModifiedAt<'_> {
+    /// Right-aligns the date if a width is given, e.g. `{:>25}`, which the
+    /// color codes would otherwise throw off
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let date = format_date(self.0);
+        let padding = f
+            .width()
+            .map_or(0, |width| width.saturating_sub(date.chars().count()));
+        write!(f, "{}{}", " ".repeat(padding), self.1.modified.paint(&date))
+    }
+}
@@ -0,0 +1,103 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use git2::{DiffOptions, Repository};
+
+/// The set of paths in a git repository that changed since a given revision,
+/// including untracked files, stored as absolute paths
+pub struct ChangedPaths {
+    files: HashSet<PathBuf>,
+    /// Every directory containing a changed file, so we can keep (and descend
+    /// into) those while pruning the rest of the tree
+    directories: HashSet<PathBuf>,
+}
+
+impl ChangedPaths {
+    /// Computes the paths changed between `rev` and the working directory of
+    /// the repository containing `path`
+    pub fn since(path: &Path, rev: &str) -> anyhow::Result<Self> {
+        let repository = Repository::discover(path)
+            .context(format!("{} is not inside a git repository", path.display()))?;
+        let workdir = repository
+            .workdir()
+            .context("Cannot compare against a bare git repository")?
+            .canonicalize()
+            .context("Failed resolving the git working directory")?;
+
+        let tree = repository
+            .revparse_single(rev)
+            .and_then(|object| object.peel_to_tree())
+            .context(format!("Could not find a commit named {rev}"))?;
+
+        let mut diff_options = DiffOptions::new();
+        diff_options
+            .include_untracked(true)
+            .recurse_untracked_dirs(true);
+
+        let diff = repository
+            .diff_tree_to_workdir_with_index(Some(&tree), Some(&mut diff_options))
+            .context(format!(
+                "Failed comparing the working directory against {rev}"
+            ))?;
+
+        let mut files = HashSet::new();
+        let mut directories = HashSet::new();
+
+        for delta in diff.deltas() {
+            for file in [delta.old_file(), delta.new_file()] {
+                let Some(relative) = file.path() else {
+                    continue;
+                };
+                let absolute = workdir.join(relative);
+                directories.extend(absolute.ancestors().skip(1).map(Path::to_path_buf));
+                files.insert(absolute);
+            }
+        }
+
+        Ok(Self { files, directories })
+    }
+
+    /// Whether `path` (absolute) changed, or is a directory containing changes
+    pub fn contains(&self, path: &Path) -> bool {
+        self.files.contains(path) || self.directories.contains(path)
+    }
+}
+
+/// Whether the directory at `path` is the root of a git repository, which
+/// has a `.git` directory, or a `.git` file for submodules and worktrees
+pub fn is_repository_root(path: &Path) -> bool {
+    path.join(".git").exists()
+}
+
+/// The absolute paths of the submodules of the repository containing `path`,
+/// and of their submodules in turn. Submodules that aren't checked out can't
+/// be opened, so their own submodules are left out
+pub fn submodule_paths(path: &Path) -> anyhow::Result<HashSet<PathBuf>> {
+    let repository = Repository::discover(path)
+        .context(format!("{} is not inside a git repository", path.display()))?;
+    let mut paths = HashSet::new();
+    add_submodules(&repository, &mut paths)?;
+    Ok(paths)
+}
+
+fn add_submodules(repository: &Repository, paths: &mut HashSet<PathBuf>) -> anyhow::Result<()> {
+    let workdir = repository
+        .workdir()
+        .context("Cannot list submodules of a bare git repository")?
+        .canonicalize()
+        .context("Failed resolving the git working directory")?;
+
+    for submodule in repository
+        .submodules()
+        .context("Failed listing git submodules")?
+    {
+        paths.insert(workdir.join(submodule.path()));
+        if let Ok(nested) = submodule.open() {
+            add_submodules(&nested, paths)?;
+        }
+    }
+    Ok(())
+}
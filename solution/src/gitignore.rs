@@ -0,0 +1,75 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+
+/// Names of the files in a directory with patterns of what to ignore within it
+const IGNORE_FILES: [&str; 2] = [".gitignore", ".ignore"];
+
+/// The rules of `.gitignore` and `.ignore` files, and of git's global
+/// excludes, for `--gitignore`
+pub struct IgnoreRules {
+    global: Gitignore,
+    /// The rules from the ignore files directly within every directory seen
+    /// so far, which are loaded once they're needed
+    directories: HashMap<PathBuf, Gitignore>,
+}
+
+impl Default for IgnoreRules {
+    fn default() -> Self {
+        let (global, _) = Gitignore::global();
+        Self {
+            global,
+            directories: HashMap::new(),
+        }
+    }
+}
+
+impl IgnoreRules {
+    /// Whether the entry at `path` is ignored. The ignore files of the
+    /// directories it's in are checked from the closest one out, up to the
+    /// root of the git repository, and the first with a matching pattern
+    /// decides, so a `!pattern` closer by can include what's ignored further
+    /// out
+    pub fn is_ignored(&mut self, path: &Path, is_dir: bool) -> bool {
+        if path.file_name().is_some_and(|name| name == ".git") {
+            return true;
+        }
+
+        for directory in path.ancestors().skip(1) {
+            let rules = self
+                .directories
+                .entry(directory.to_path_buf())
+                .or_insert_with(|| load(directory));
+            match rules.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+            // Ignore files outside the repository don't apply to it
+            if directory.join(".git").exists() {
+                break;
+            }
+        }
+
+        self.global.matched(path, is_dir).is_ignore()
+    }
+}
+
+/// The rules from the ignore files directly within `directory`. Invalid
+/// patterns are skipped, like git does
+fn load(directory: &Path) -> Gitignore {
+    let mut builder = GitignoreBuilder::new(directory);
+    for file in IGNORE_FILES {
+        let file = directory.join(file);
+        if file.is_file() {
+            builder.add(file);
+        }
+    }
+    builder.build().unwrap_or_else(|_| Gitignore::empty())
+}
@@ -0,0 +1,94 @@
+use std::{fs::File, io::Read, path::Path};
+
+use clap::ValueEnum;
+use sha2::Digest;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum HashAlgorithm {
+    Md5,
+    Sha1,
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// How many hex digits the digest has
+    pub fn hex_len(self) -> usize {
+        match self {
+            Self::Md5 => 32,
+            Self::Sha1 => 40,
+            Self::Sha256 | Self::Blake3 => 64,
+        }
+    }
+}
+
+enum Hasher {
+    Md5(md5::Md5),
+    Sha1(sha1::Sha1),
+    Sha256(sha2::Sha256),
+    Blake3(Box<blake3::Hasher>),
+}
+
+impl Hasher {
+    fn new(algorithm: HashAlgorithm) -> Self {
+        match algorithm {
+            HashAlgorithm::Md5 => Self::Md5(md5::Md5::new()),
+            HashAlgorithm::Sha1 => Self::Sha1(sha1::Sha1::new()),
+            HashAlgorithm::Sha256 => Self::Sha256(sha2::Sha256::new()),
+            HashAlgorithm::Blake3 => Self::Blake3(Box::default()),
+        }
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Md5(hasher) => hasher.update(data),
+            Self::Sha1(hasher) => hasher.update(data),
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Blake3(hasher) => {
+                hasher.update(data);
+            }
+        }
+    }
+
+    /// The digest as a lowercase hex string, like `sha256sum` prints it
+    fn finish(self) -> String {
+        let digest: Vec<u8> = match self {
+            Self::Md5(hasher) => hasher.finalize().to_vec(),
+            Self::Sha1(hasher) => hasher.finalize().to_vec(),
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Blake3(hasher) => hasher.finalize().as_bytes().to_vec(),
+        };
+        digest.iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+}
+
+/// Size of the chunks files are read in, so we never hold a whole file in memory
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Hashes the contents of the file at `path`, reading it in chunks
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    hash_reader(File::open(path)?, algorithm)
+}
+
+/// Hashes only the first `limit` bytes of the file at `path`
+pub fn hash_file_start(
+    path: &Path,
+    algorithm: HashAlgorithm,
+    limit: u64,
+) -> std::io::Result<String> {
+    hash_reader(File::open(path)?.take(limit), algorithm)
+}
+
+fn hash_reader(mut file: impl Read, algorithm: HashAlgorithm) -> std::io::Result<String> {
+    let mut hasher = Hasher::new(algorithm);
+    let mut buffer = vec![0; CHUNK_SIZE];
+
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(hasher.finish());
+        }
+        hasher.update(&buffer[..read]);
+    }
+}
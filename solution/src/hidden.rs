@@ -0,0 +1,92 @@
+use std::{ffi::OsStr, fs::Metadata};
+
+use walkdir::DirEntry;
+
+/// Whether the walked `entry` is hidden. The root of the walk never is, as
+/// it was asked for by name, even if it's something like `.`
+pub fn is_hidden(entry: &DirEntry) -> bool {
+    entry.depth() > 0 && is_hidden_entry(entry.file_name(), || entry.metadata().ok())
+}
+
+/// Whether the entry named `name` is hidden, by being a dotfile or, on
+/// Windows, by having the hidden attribute in its `metadata`. `metadata` is
+/// only read on Windows, and only for names without a leading dot
+pub fn is_hidden_entry(name: &OsStr, metadata: impl FnOnce() -> Option<Metadata>) -> bool {
+    is_dotfile(name) || platform::has_hidden_attribute(metadata)
+}
+
+fn is_dotfile(name: &OsStr) -> bool {
+    name.to_str().map(|s| s.starts_with('.')).unwrap_or(false)
+}
+
+#[cfg(windows)]
+mod platform {
+    use std::{fs::Metadata, os::windows::fs::MetadataExt};
+
+    const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+
+    pub fn has_hidden_attribute(metadata: impl FnOnce() -> Option<Metadata>) -> bool {
+        metadata().is_some_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0)
+    }
+}
+
+/// Other platforms have no attribute for hiding files, only the dot
+#[cfg(not(windows))]
+mod platform {
+    use std::fs::Metadata;
+
+    pub fn has_hidden_attribute(_metadata: impl FnOnce() -> Option<Metadata>) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn dotfiles_are_hidden() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join(".hidden");
+        fs::write(&path, "").unwrap();
+
+        assert!(is_hidden_entry(OsStr::new(".hidden"), || path
+            .metadata()
+            .ok()));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn other_files_are_not_hidden() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("shown");
+        fs::write(&path, "").unwrap();
+
+        assert!(!is_hidden_entry(OsStr::new("shown"), || path
+            .metadata()
+            .ok()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn files_with_the_hidden_attribute_are_hidden() {
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("shown");
+        fs::write(&path, "").unwrap();
+        assert!(!is_hidden_entry(OsStr::new("shown"), || path
+            .metadata()
+            .ok()));
+
+        let status = std::process::Command::new("attrib")
+            .arg("+h")
+            .arg(&path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        assert!(is_hidden_entry(OsStr::new("shown"), || path
+            .metadata()
+            .ok()));
+    }
+}
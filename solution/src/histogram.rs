@@ -0,0 +1,45 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+
+use crate::theme::Theme;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Histogram {
+    /// Bars proportional to the size of every file
+    Size,
+}
+
+/// How many characters the longest bar takes up
+pub const WIDTH: usize = 20;
+
+/// Partially filled block characters, from one to seven eighths
+const EIGHTHS: [char; 7] = ['▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// A bar showing `size` relative to `largest`, padded to a fixed width and
+/// styled by the theme's bar styles as it grows
+pub struct SizeBar<'theme> {
+    pub size: u64,
+    pub largest: u64,
+    pub theme: &'theme Theme,
+}
+
+impl Display for SizeBar<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let eighths = if self.largest == 0 {
+            0
+        } else {
+            // Widen to avoid overflowing on huge files
+            (u128::from(self.size) * (WIDTH * 8) as u128 / u128::from(self.largest)) as u64
+        };
+
+        let mut bar = "█".repeat((eighths / 8) as usize);
+        if eighths % 8 > 0 {
+            bar.push(EIGHTHS[(eighths % 8 - 1) as usize]);
+        }
+        let bar = format!("{bar:<WIDTH$}");
+
+        let magnitude = (eighths as usize * 3 / (WIDTH * 8)).min(2);
+        write!(f, "{}", self.theme.bars[magnitude].paint(&bar))
+    }
+}
@@ -0,0 +1,73 @@
+use std::{io::Write, path::Path};
+
+use bytesize::ByteSize;
+
+use crate::record::Record;
+
+/// Styling mirroring the terminal colors, with a class per kind of entry
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; margin: 2em; color: #222; }
+table { border-collapse: collapse; }
+th { text-align: left; border-bottom: 2px solid #888; }
+th, td { padding: 0.2em 1em; }
+tr:nth-child(even) { background: #f4f4f4; }
+td.size { text-align: right; color: #2a7a2a; font-family: monospace; }
+td.modified { color: #2a4f9a; font-family: monospace; }
+tr.dir td.path { color: #2a4f9a; font-weight: bold; }
+tr.symlink td.path { color: #9a7a00; font-style: italic; }
+tr.other td.path { color: #9a7a00; }
+";
+
+/// Writes a self-contained HTML page with a table of `records`, titled
+/// after the `root` that was listed
+pub fn write(records: &[Record], root: &Path, mut output: impl Write) -> std::io::Result<()> {
+    let title = escape(&root.to_string_lossy());
+
+    writeln!(output, "<!DOCTYPE html>")?;
+    writeln!(output, "<html>")?;
+    writeln!(output, "<head>")?;
+    writeln!(output, "<meta charset=\"utf-8\">")?;
+    writeln!(output, "<title>{title}</title>")?;
+    writeln!(output, "<style>\n{STYLE}</style>")?;
+    writeln!(output, "</head>")?;
+    writeln!(output, "<body>")?;
+    writeln!(output, "<h1>{title}</h1>")?;
+    writeln!(output, "<table>")?;
+    writeln!(
+        output,
+        "<tr><th>Path</th><th>Size</th><th>Modified at</th></tr>"
+    )?;
+
+    for record in records {
+        let path = match &record.target {
+            Some(target) => format!("{} → {}", escape(&record.path), escape(target)),
+            None => escape(&record.path),
+        };
+        let modified = record
+            .modified
+            .as_deref()
+            .and_then(|modified| chrono::DateTime::parse_from_rfc3339(modified).ok())
+            .map(|modified| modified.format("%Y-%m-%d %H:%M:%S").to_string())
+            .unwrap_or_default();
+
+        writeln!(
+            output,
+            "<tr class=\"{}\"><td class=\"path\">{path}</td><td class=\"size\" title=\"{} bytes\">{}</td><td class=\"modified\">{modified}</td></tr>",
+            record.kind,
+            record.size,
+            ByteSize(record.size)
+        )?;
+    }
+
+    writeln!(output, "</table>")?;
+    writeln!(output, "</body>")?;
+    writeln!(output, "</html>")
+}
+
+/// Escapes the characters with special meaning in HTML
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
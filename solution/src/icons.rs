@@ -0,0 +1,101 @@
+use std::fs::FileType;
+
+use clap::ValueEnum;
+
+use crate::filter;
+
+/// The glyphs `--icons` shows in front of names
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum IconSet {
+    /// Icons from a Nerd Font, which the terminal's font needs to include
+    #[default]
+    NerdFont,
+    /// Single characters that work in any font: `/` for directories, `@` for
+    /// symlinks, `*` for source code, `#` for images, `~` for audio and video,
+    /// `%` for archives and `-` for other files
+    Ascii,
+}
+
+/// What kind of thing an icon shows
+#[derive(Clone, Copy)]
+enum Category {
+    Directory,
+    Symlink,
+    Rust,
+    Python,
+    JavaScript,
+    Code,
+    Markdown,
+    Text,
+    Config,
+    Image,
+    Audio,
+    Video,
+    Archive,
+    File,
+}
+
+impl Category {
+    fn of(file_type: FileType, name: &str) -> Self {
+        if file_type.is_dir() {
+            return Self::Directory;
+        }
+        if file_type.is_symlink() {
+            return Self::Symlink;
+        }
+
+        let Some(extension) = filter::extension(name, true) else {
+            return Self::File;
+        };
+        match extension.as_str() {
+            "rs" => Self::Rust,
+            "py" => Self::Python,
+            "js" | "mjs" | "cjs" | "ts" | "jsx" | "tsx" => Self::JavaScript,
+            "c" | "h" | "cpp" | "hpp" | "cc" | "go" | "java" | "kt" | "rb" | "sh" | "swift"
+            | "zig" => Self::Code,
+            "md" | "markdown" => Self::Markdown,
+            "txt" | "log" | "csv" | "tsv" => Self::Text,
+            "toml" | "yaml" | "yml" | "json" | "ini" | "conf" | "cfg" | "lock" => Self::Config,
+            "png" | "jpg" | "jpeg" | "gif" | "bmp" | "svg" | "webp" | "ico" | "tiff" => Self::Image,
+            "mp3" | "flac" | "wav" | "ogg" | "m4a" | "opus" => Self::Audio,
+            "mp4" | "mkv" | "mov" | "avi" | "webm" => Self::Video,
+            "zip" | "tar" | "gz" | "tgz" | "xz" | "bz2" | "zst" | "7z" | "rar" => Self::Archive,
+            _ => Self::File,
+        }
+    }
+}
+
+impl IconSet {
+    /// The icon of an entry named `name` with `file_type`, which shouldn't
+    /// follow symlinks so they get an icon of their own
+    pub fn icon(self, file_type: FileType, name: &str) -> &'static str {
+        let category = Category::of(file_type, name);
+        match self {
+            Self::NerdFont => match category {
+                Category::Directory => "\u{f07b}",
+                Category::Symlink => "\u{f0c1}",
+                Category::Rust => "\u{e7a8}",
+                Category::Python => "\u{e73c}",
+                Category::JavaScript => "\u{e74e}",
+                Category::Code => "\u{f121}",
+                Category::Markdown => "\u{e73e}",
+                Category::Text => "\u{f15c}",
+                Category::Config => "\u{e615}",
+                Category::Image => "\u{f1c5}",
+                Category::Audio => "\u{f1c7}",
+                Category::Video => "\u{f1c8}",
+                Category::Archive => "\u{f1c6}",
+                Category::File => "\u{f15b}",
+            },
+            Self::Ascii => match category {
+                Category::Directory => "/",
+                Category::Symlink => "@",
+                Category::Rust | Category::Python | Category::JavaScript | Category::Code => "*",
+                Category::Image => "#",
+                Category::Audio | Category::Video => "~",
+                Category::Archive => "%",
+                Category::Markdown | Category::Text | Category::Config | Category::File => "-",
+            },
+        }
+    }
+}
@@ -0,0 +1,92 @@
+use std::fs::FileType;
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+
+use clap::{builder::PossibleValue, ValueEnum};
+
+/// The kind of file an entry is, as used by `--only-type` or `--type`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FileKind {
+    Regular,
+    Dir,
+    Symlink,
+    #[cfg(unix)]
+    Socket,
+    #[cfg(unix)]
+    Fifo,
+    #[cfg(unix)]
+    Block,
+    #[cfg(unix)]
+    Char,
+}
+
+impl FileKind {
+    /// The kind of `file_type`, if it's one we know about
+    pub fn of(file_type: FileType) -> Option<Self> {
+        if file_type.is_file() {
+            return Some(Self::Regular);
+        }
+        if file_type.is_dir() {
+            return Some(Self::Dir);
+        }
+        if file_type.is_symlink() {
+            return Some(Self::Symlink);
+        }
+
+        #[cfg(unix)]
+        {
+            if file_type.is_socket() {
+                return Some(Self::Socket);
+            }
+            if file_type.is_fifo() {
+                return Some(Self::Fifo);
+            }
+            if file_type.is_block_device() {
+                return Some(Self::Block);
+            }
+            if file_type.is_char_device() {
+                return Some(Self::Char);
+            }
+        }
+
+        None
+    }
+}
+
+// Written by hand rather than derived, since the special kinds only exist on Unix
+impl ValueEnum for FileKind {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Self::Regular,
+            Self::Dir,
+            Self::Symlink,
+            #[cfg(unix)]
+            Self::Socket,
+            #[cfg(unix)]
+            Self::Fifo,
+            #[cfg(unix)]
+            Self::Block,
+            #[cfg(unix)]
+            Self::Char,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        let value = match self {
+            Self::Regular => PossibleValue::new("regular")
+                .alias("file")
+                .help("Regular files, also accepted as `file`"),
+            Self::Dir => PossibleValue::new("dir").help("Directories"),
+            Self::Symlink => PossibleValue::new("symlink").help("Symbolic links"),
+            #[cfg(unix)]
+            Self::Socket => PossibleValue::new("socket").help("Unix domain sockets"),
+            #[cfg(unix)]
+            Self::Fifo => PossibleValue::new("fifo").help("Named pipes"),
+            #[cfg(unix)]
+            Self::Block => PossibleValue::new("block").help("Block devices"),
+            #[cfg(unix)]
+            Self::Char => PossibleValue::new("char").help("Character devices"),
+        };
+        Some(value)
+    }
+}
@@ -0,0 +1,50 @@
+//! The traversal and formatting behind `explore`, for embedding in other
+//! programs. [`Explorer`] walks a directory tree into [`EntryInfo`]s, which the
+//! [`FormatEntry`] and the other formatters display
+
+pub mod analyze;
+pub mod boot;
+pub mod cache;
+pub mod columns;
+pub mod content;
+pub mod count_by;
+pub mod du;
+pub mod dupes;
+pub mod entry;
+pub mod errors;
+pub mod exec;
+pub mod explorer;
+pub mod filter;
+pub mod follow;
+pub mod format;
+pub mod git;
+pub mod gitignore;
+pub mod hash;
+pub mod hidden;
+pub mod histogram;
+pub mod html;
+pub mod icons;
+pub mod kind;
+pub mod long;
+pub mod newest;
+pub mod output;
+pub mod parallel;
+pub mod parquet_file;
+pub mod paths;
+pub mod record;
+pub mod snapshot;
+pub mod sort;
+mod stats;
+pub mod summary;
+pub mod template;
+pub mod theme;
+pub mod tree_json;
+pub mod tui;
+pub mod verify;
+pub mod watch;
+
+pub use entry::Entry as EntryInfo;
+pub use explorer::Explorer;
+pub use format::{FormatEntry, FormatModifiedAt, FormatSize};
+pub use hidden::{is_hidden, is_hidden_entry};
+pub use stats::Stats;
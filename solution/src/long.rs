@@ -0,0 +1,138 @@
+use std::fs::Metadata;
+
+use crate::theme::Theme;
+
+/// The permissions, owner and group columns of `--long`, like `ls -l`
+#[derive(Default)]
+pub struct LongColumns {
+    /// Looks up the names of owners and groups, remembering them for the
+    /// next entry with the same
+    #[cfg(unix)]
+    users: users::UsersCache,
+}
+
+/// How wide the owner and group columns are padded to
+const NAME_WIDTH: usize = 8;
+
+impl LongColumns {
+    /// The headers of the columns, separated by tabs
+    pub fn headers(theme: &Theme) -> String {
+        // Pad after painting so the padding isn't underlined
+        let header = |header: &str, width: usize| {
+            let padding = " ".repeat(width.saturating_sub(header.len()));
+            format!("{}{padding}", theme.header.paint(header))
+        };
+        [
+            header("Mode", 10),
+            header("Owner", NAME_WIDTH),
+            header("Group", NAME_WIDTH),
+        ]
+        .join("\t")
+    }
+
+    /// The columns for an entry, separated by tabs. Permissions are shown like
+    /// `drwxr-xr-x`, and owners and groups by name where they have one
+    #[cfg(unix)]
+    pub fn format(&self, metadata: &Metadata) -> String {
+        use std::os::unix::fs::MetadataExt;
+        use users::{Groups, Users};
+
+        let owner = self
+            .users
+            .get_user_by_uid(metadata.uid())
+            .map_or(metadata.uid().to_string(), |user| {
+                user.name().to_string_lossy().into_owned()
+            });
+        let group = self
+            .users
+            .get_group_by_gid(metadata.gid())
+            .map_or(metadata.gid().to_string(), |group| {
+                group.name().to_string_lossy().into_owned()
+            });
+
+        format!(
+            "{}\t{owner:<NAME_WIDTH$}\t{group:<NAME_WIDTH$}",
+            permissions(metadata)
+        )
+    }
+
+    /// The columns for an entry, separated by tabs. Windows has no Unix
+    /// permissions, so the readonly (`r`), hidden (`h`) and system (`s`)
+    /// attributes are shown instead, and no owner or group
+    #[cfg(windows)]
+    pub fn format(&self, metadata: &Metadata) -> String {
+        use std::os::windows::fs::MetadataExt;
+        const READONLY: u32 = 0x1;
+        const HIDDEN: u32 = 0x2;
+        const SYSTEM: u32 = 0x4;
+
+        let attributes = metadata.file_attributes();
+        let flag = |attribute, c| if attributes & attribute != 0 { c } else { '-' };
+        let mode: String = [
+            if metadata.is_dir() { 'd' } else { '-' },
+            flag(READONLY, 'r'),
+            flag(HIDDEN, 'h'),
+            flag(SYSTEM, 's'),
+        ]
+        .into_iter()
+        .collect();
+
+        format!("{mode:<10}\t{:<NAME_WIDTH$}\t{:<NAME_WIDTH$}", "", "")
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    pub fn format(&self, _metadata: &Metadata) -> String {
+        format!("{:<10}\t{:<NAME_WIDTH$}\t{:<NAME_WIDTH$}", "", "", "")
+    }
+}
+
+/// The type and permission bits of `metadata` like `ls -l` shows them, e.g.
+/// `drwxr-xr-x`, including setuid, setgid and sticky bits
+#[cfg(unix)]
+fn permissions(metadata: &Metadata) -> String {
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+
+    let file_type = metadata.file_type();
+    let kind = if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_block_device() {
+        'b'
+    } else if file_type.is_char_device() {
+        'c'
+    } else if file_type.is_fifo() {
+        'p'
+    } else if file_type.is_socket() {
+        's'
+    } else {
+        '-'
+    };
+
+    let mode = metadata.mode();
+    let bit = |mask, c| if mode & mask != 0 { c } else { '-' };
+    // The execute position also shows the special bits, in lowercase if the
+    // entry is executable as well
+    let execute =
+        |mask, special, executable, not_executable| match (mode & mask != 0, mode & special != 0) {
+            (true, true) => executable,
+            (false, true) => not_executable,
+            (true, false) => 'x',
+            (false, false) => '-',
+        };
+
+    [
+        kind,
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        execute(0o100, 0o4000, 's', 'S'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        execute(0o010, 0o2000, 's', 'S'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        execute(0o001, 0o1000, 't', 'T'),
+    ]
+    .into_iter()
+    .collect()
+}
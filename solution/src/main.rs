@@ -1,10 +1,29 @@
-use std::{fmt::Display, fs::Metadata, path::PathBuf, time::SystemTime};
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    io::{ErrorKind, IsTerminal, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
 
 use anyhow::Context;
-use bytesize::ByteSize;
 use clap::Parser;
 use colored::Colorize;
-use walkdir::{DirEntry, WalkDir};
+use indicatif::{ProgressBar, ProgressStyle};
+
+use explore::{
+    analyze, boot, cache,
+    columns::{Column, ENTRIES_HEADER, LINES_HEADER},
+    content, count_by, du, dupes, entry,
+    entry::Entry,
+    errors, exec, filter, follow, format,
+    format::{FormatEntry, FormatModifiedAt, FormatSize},
+    git, hash, histogram, html, icons, is_hidden_entry, kind, long, newest, output, parquet_file,
+    paths::{self, absolute_entry_path, absolute_path, relative_to, resolve_dots},
+    record, snapshot, sort, summary, template, theme, tree_json, tui, verify, watch, Explorer,
+};
 
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
@@ -12,11 +31,14 @@ struct Options {
     #[arg(short, long)]
     path: Option<PathBuf>,
 
+    /// Only list entries at least this deep. Depth 0 is the directory being
+    /// listed itself, and 1 the entries directly within it
     #[arg(long, default_value_t = 1)]
     min_depth: usize,
 
-    #[arg(long, default_value_t = 1)]
-    max_depth: usize,
+    /// Don't list or descend into entries deeper than this. Unlimited by default
+    #[arg(long)]
+    max_depth: Option<usize>,
 
     #[arg(long, default_value_t = false)]
     headers: bool,
@@ -26,114 +48,1686 @@ struct Options {
 
     #[arg(long, default_value_t = false)]
     modified: bool,
+
+    /// Show a counter of processed entries on stderr while walking.
+    /// Disabled automatically when stdout or stderr isn't a terminal
+    #[arg(long, default_value_t = false)]
+    progress: bool,
+
+    /// Exit with code 2 if no entries were listed. Other exit codes are
+    /// 0 when entries were listed and 1 when an error occurred
+    #[arg(long, default_value_t = false)]
+    fail_on_empty: bool,
+
+    /// Only list paths changed in git since the given commit or ref,
+    /// including untracked files
+    #[arg(long, value_name = "REV")]
+    since_commit: Option<String>,
+
+    /// Run a command for every listed entry instead of printing it. `{}` is
+    /// replaced by the entry's path, otherwise the path is appended. Every
+    /// argument up to a `;` (quoted as `';'` in most shells) is part of the
+    /// command, so it must end with one when any options come after it
+    #[arg(
+        long,
+        num_args = 1..,
+        value_terminator = ";",
+        allow_hyphen_values = true,
+        value_name = "COMMAND"
+    )]
+    exec: Option<Vec<String>>,
+
+    /// Print the commands `--exec` would run without running them
+    #[arg(long, requires = "exec", default_value_t = false)]
+    dry_run: bool,
+
+    /// Collapse chains of directories only containing a single directory
+    /// into one `a/b/c` entry
+    #[arg(long, default_value_t = false)]
+    flatten: bool,
+
+    /// Print how many entries were visited, how many stat calls were made and
+    /// where the time went to stderr after the listing
+    #[arg(long, default_value_t = false)]
+    stats: bool,
+
+    /// Show the total size of everything within directories instead of the
+    /// size of the directory itself, and the total of the whole tree at the
+    /// end
+    #[arg(long, visible_alias = "total-size", default_value_t = false)]
+    du: bool,
+
+    /// Reuse what a previous `--du` run stored in this file about directories
+    /// that haven't been modified since, without listing them or stat-ing
+    /// their files, and update it afterwards. Changing a file in place doesn't
+    /// modify its directory, so its old size is used until an entry in the
+    /// same directory is added, removed or renamed
+    #[arg(long, requires = "du", value_name = "FILE")]
+    cache: Option<PathBuf>,
+
+    /// Show canonical absolute paths instead of names. Paths that can't be
+    /// canonicalized, like broken symlinks, are made absolute as they are
+    #[arg(long, default_value_t = false)]
+    absolute_paths: bool,
+
+    /// Only print the path of every entry, each terminated by a NUL
+    /// character, for use with e.g. `xargs -0`
+    #[arg(long, default_value_t = false)]
+    print0: bool,
+
+    /// Show the listing in `$PAGER`, or `less -R` if it isn't set, even when
+    /// it fits on the terminal. Ignored when stdout isn't a terminal
+    #[arg(long, default_value_t = false)]
+    pager: bool,
+
+    /// Never show the listing in a pager. Without it or `--pager`, listings
+    /// too long for the terminal are shown in one, unless `--progress`,
+    /// `--exec` or `--watch` is given
+    #[arg(long, default_value_t = false, conflicts_with = "pager")]
+    no_pager: bool,
+
+    /// Sort the whole listing instead of listing entries as they are found.
+    /// Entries are shown by their path, as they no longer follow their parents
+    #[arg(long, value_enum, value_name = "KEY")]
+    sort: Option<sort::SortKey>,
+
+    /// Seed for `--sort random`, to get the same shuffle every time
+    #[arg(long, requires = "sort")]
+    seed: Option<u64>,
+
+    /// Reverse the order of `--sort`, e.g. to list the smallest first
+    #[arg(long, requires = "sort", default_value_t = false)]
+    reverse: bool,
+
+    /// Only list files whose path below the root matches this glob, like
+    /// `*.rs`. Can be repeated to list files matching any of them
+    #[arg(long, value_name = "GLOB")]
+    include: Vec<String>,
+
+    /// Skip entries whose path below the root matches this glob, like
+    /// `target/**`, without descending into them. Can be repeated
+    #[arg(long, value_name = "GLOB")]
+    exclude: Vec<String>,
+
+    /// Show permissions, owner and group in front of every entry, like
+    /// `ls -l`. On Windows, the readonly, hidden and system attributes are
+    /// shown instead
+    #[arg(short, long, default_value_t = false)]
+    long: bool,
+
+    /// Draw the tree with lines connecting every entry to its siblings and
+    /// parent, like `tree`, instead of indenting names. This needs the whole
+    /// listing before anything is printed
+    #[arg(long, default_value_t = false, conflicts_with_all = ["sort", "plain", "no_indent"])]
+    tree: bool,
+
+    /// Skip entries ignored by `.gitignore` or `.ignore` files, or by git's
+    /// global excludes, along with the `.git` directory itself
+    #[arg(long, default_value_t = false)]
+    gitignore: bool,
+
+    /// Walk the tree, read the metadata of entries, count their lines or
+    /// children and hash them on this many threads. With more than one, the
+    /// listing starts once the whole tree is walked, and directories' contents
+    /// are listed by name
+    #[arg(long, value_name = "N", default_value_t = 1)]
+    threads: usize,
+
+    /// With `--threads`, list entries in the order they're found instead,
+    /// which changes from run to run and mixes directories' contents, but
+    /// saves putting them in order
+    #[arg(long, requires = "threads", default_value_t = false)]
+    unordered: bool,
+
+    /// Print how many files, directories and symlinks were listed and the
+    /// total size of the files after the listing
+    #[arg(long, default_value_t = false)]
+    summary: bool,
+
+    /// Only list files at least this large, e.g. `10MB` or `1.5GiB`
+    #[arg(long, value_name = "SIZE")]
+    min_size: Option<bytesize::ByteSize>,
+
+    /// Only list files at most this large, e.g. `10MB` or `1.5GiB`
+    #[arg(long, value_name = "SIZE")]
+    max_size: Option<bytesize::ByteSize>,
+
+    /// Only list files modified after this, a date like `2024-01-01` or a
+    /// duration ago like `7d`
+    #[arg(long, value_name = "TIME")]
+    newer_than: Option<filter::TimePoint>,
+
+    /// Only list files modified before this, a date like `2024-01-01` or a
+    /// duration ago like `7d`
+    #[arg(long, value_name = "TIME")]
+    older_than: Option<filter::TimePoint>,
+
+    /// Show an icon of the kind of every entry in front of its name, from a
+    /// Nerd Font unless `ascii` is given
+    #[arg(
+        long,
+        value_enum,
+        value_name = "SET",
+        num_args = 0..=1,
+        default_missing_value = "nerd-font"
+    )]
+    icons: Option<icons::IconSet>,
+
+    /// Instead of listing entries, keep running and print the entries created
+    /// (+), modified (~) or removed (-) as it happens. `--hidden`, the depth
+    /// limits and the name and path filters decide which are shown
+    #[arg(long, default_value_t = false)]
+    watch: bool,
+
+    /// Which columns to show, in order and separated by commas, e.g.
+    /// `size,modified,permissions,name`. Without it, the columns are `size`
+    /// and `name` along with those added by `--long`, `--histogram`,
+    /// `--count-lines`, `--hash`, `--modified` and `--ctime`
+    #[arg(long, value_enum, value_name = "COLUMNS", value_delimiter = ',')]
+    columns: Vec<Column>,
+
+    /// Stop after listing this many entries
+    #[arg(long, value_name = "N")]
+    limit: Option<usize>,
+
+    /// Only list entries of this kind. Can be repeated to list several kinds.
+    /// Directories are still descended into
+    #[arg(long, visible_alias = "type", value_enum, value_name = "KIND")]
+    only_type: Vec<kind::FileKind>,
+
+    /// How to print the listing. The machine-readable formats have no colors
+    /// or headers
+    #[arg(long, value_enum, default_value_t = output::Format::Text)]
+    format: output::Format,
+
+    /// Browse the directory interactively instead of listing it. Use the
+    /// arrow keys to move, Enter to open a directory, Backspace to go up, `/`
+    /// to search and `q` to quit
+    #[arg(long, visible_alias = "interactive", default_value_t = false)]
+    tui: bool,
+
+    /// Show the size of what symlinks point to rather than of the links
+    /// themselves. Broken symlinks keep the size of the link
+    #[arg(long, default_value_t = false)]
+    resolve_targets_size: bool,
+
+    /// Show a checksum of every file's contents with this algorithm, e.g. for
+    /// a manifest. Also the algorithm used by `--checksum-verify` and `dupes`,
+    /// sha256 by default
+    #[arg(long, value_enum, value_name = "ALGORITHM")]
+    hash: Option<hash::HashAlgorithm>,
+
+    /// Instead of listing, check the files in a `sha256sum`-style file of
+    /// `<hash>  <path>` lines, with paths relative to `--path`. Exits with
+    /// code 1 if any file is missing or doesn't match
+    #[arg(long, value_name = "SUMS_FILE")]
+    checksum_verify: Option<PathBuf>,
+
+    /// Hide directories directly containing fewer than this many entries.
+    /// This reads every listed directory, which is slow on huge trees
+    #[arg(long, value_name = "N")]
+    min_count: Option<usize>,
+
+    /// Show paths relative to this directory instead of names. Paths outside
+    /// of it are shown in full
+    #[arg(long, value_name = "BASE")]
+    relative_to: Option<PathBuf>,
+
+    /// Only list entries whose name matches this glob. Can be repeated to
+    /// list entries matching any of them
+    #[arg(long, value_name = "GLOB")]
+    glob: Vec<String>,
+
+    /// Only list entries whose name matches this regular expression
+    #[arg(long, value_name = "REGEX")]
+    regex: Option<String>,
+
+    /// Only list files with this extension. Can be repeated
+    #[arg(long, value_name = "EXTENSION")]
+    ext: Vec<String>,
+
+    /// Hide files with this extension, ignoring case. Can be repeated
+    #[arg(long, value_name = "EXTENSION")]
+    exclude_ext: Vec<String>,
+
+    /// Only list entries whose name is at most this many characters long
+    #[arg(long, value_name = "N")]
+    max_name_length: Option<usize>,
+
+    /// Only list entries whose name is at least this many characters long,
+    /// e.g. to find names too long for another filesystem
+    #[arg(long, value_name = "N")]
+    min_name_length: Option<usize>,
+
+    /// Measure name lengths in UTF-8 bytes rather than characters
+    #[arg(long, default_value_t = false)]
+    name_length_bytes: bool,
+
+    /// Match names case-insensitively in `--glob`, `--regex` and `--ext`
+    #[arg(short, long, default_value_t = false)]
+    ignore_case: bool,
+
+    /// Don't descend into nested git repositories, like submodules, below
+    /// the directory being listed
+    #[arg(long, default_value_t = false)]
+    stay_in_repo: bool,
+
+    /// Only list directories, one line each with how many entries they
+    /// directly contain and the total size of everything within them
+    #[arg(long, default_value_t = false)]
+    compact: bool,
+
+    /// Only print the headers once the first entry is listed, so an empty
+    /// listing prints nothing at all
+    #[arg(long, requires = "headers", default_value_t = false)]
+    no_headers_when_empty: bool,
+
+    /// Flag entries in red whose modification time is earlier than their
+    /// creation time, e.g. after tampering or copies preserving timestamps
+    #[arg(long, default_value_t = false)]
+    anomalies: bool,
+
+    /// Draw a bar next to every file, scaled to the largest file listed.
+    /// This needs the whole listing before printing anything
+    #[arg(long, value_enum, value_name = "KIND")]
+    histogram: Option<histogram::Histogram>,
+
+    /// Follow symlinks, but only descend into directories reached through at
+    /// most this many of them, so chains of links can't lead the walk astray
+    #[arg(long, value_name = "N", group = "following")]
+    follow_depth: Option<usize>,
+
+    /// Follow symlinks, descending into the directories they point to and
+    /// showing the kind and size of what they point to. Links pointing to
+    /// nothing are listed as links, and links pointing back to one of their
+    /// ancestors are reported on stderr and skipped
+    #[arg(long, group = "following", default_value_t = false)]
+    follow: bool,
+
+    /// Fail on the first symlink pointing back to one of its ancestors,
+    /// rather than reporting and skipping it
+    #[arg(long, requires = "following", default_value_t = false)]
+    fail_on_symlink_loop: bool,
+
+    /// Only list directories where nothing within has been modified for
+    /// this long, e.g. `90d` or `1y 6months`
+    #[arg(long, value_name = "DURATION", value_parser = humantime::parse_duration)]
+    stale: Option<Duration>,
+
+    /// Plain `size\tname` lines for scripts, without colors, styled headers
+    /// or indentation
+    #[arg(long, default_value_t = false)]
+    plain: bool,
+
+    /// How to show the size of directories, unless `--du` shows the size of
+    /// their contents instead
+    #[arg(long, value_enum, value_name = "STYLE", default_value_t)]
+    dir_size: format::DirSize,
+
+    /// Only list directories, each with the most recently modified file
+    /// anywhere within it and when that was. Rank them with `--sort modified`
+    #[arg(long, default_value_t = false)]
+    newest_in_dir: bool,
+
+    /// Flush every entry as soon as it's listed, even when stdout isn't a
+    /// terminal, e.g. for a slow consumer at the other end of a pipe
+    #[arg(long, default_value_t = false)]
+    line_buffered: bool,
+
+    /// Show how many hidden entries every directory has on one line instead
+    /// of listing them
+    #[arg(long, default_value_t = false)]
+    collapse_hidden: bool,
+
+    /// Instead of listing, print the total size of everything at this path,
+    /// like `du -sh`
+    #[arg(long, value_name = "PATH")]
+    size_of: Option<PathBuf>,
+
+    /// Count files with several hard links once in total sizes, like `du`.
+    /// Only supported on Unix, elsewhere every link is counted
+    #[arg(long, default_value_t = false)]
+    deduplicate_hardlinks: bool,
+
+    /// Instead of listing, print the JSON Schema of `--format json`'s output
+    #[arg(long, default_value_t = false)]
+    export_schema: bool,
+
+    /// Colors to use, e.g. `light` for terminals with a light background
+    #[arg(long, value_enum, value_name = "THEME", default_value_t)]
+    theme: theme::ThemeName,
+
+    /// When to use colors. `auto` colors when stdout is a terminal, unless
+    /// the `NO_COLOR` environment variable is set
+    #[arg(long, value_enum, value_name = "WHEN", default_value_t)]
+    color: theme::ColorWhen,
+
+    /// TOML file with styles replacing those of `--theme`, for kinds of
+    /// entries like `dir = "blue"` and for files by extension in an
+    /// `[extensions]` table like `rs = { color = "red", bold = true }`
+    #[arg(long, value_name = "FILE")]
+    theme_file: Option<PathBuf>,
+
+    /// Stop listing before the sizes of the files listed add up to more than
+    /// this, e.g. `500MB`. With `--sort`, this applies to the sorted listing
+    #[arg(long, value_name = "SIZE")]
+    max_total_size: Option<bytesize::ByteSize>,
+
+    /// Show when the inode last changed, which includes permission and owner
+    /// changes. This is Unix's ctime, not the creation time, and only
+    /// available on Unix
+    #[arg(long, default_value_t = false)]
+    ctime: bool,
+
+    /// List the paths in this file, one per line, instead of walking a
+    /// directory. Paths that don't exist anymore are shown as missing
+    #[arg(long, value_name = "FILE")]
+    entries_from: Option<PathBuf>,
+
+    /// Show every entry's path relative to the directory being listed instead
+    /// of its indented name, so every line is a usable path
+    #[arg(long, default_value_t = false)]
+    no_indent: bool,
+
+    /// Warn about entries that can't be read, e.g. for lack of permissions,
+    /// and carry on instead of stopping
+    #[arg(long, default_value_t = false)]
+    skip_errors: bool,
+
+    /// Instead of warning about every skipped entry as it's found, report
+    /// them at the end grouped by the kind of error
+    #[arg(long, requires = "skip_errors", default_value_t = false)]
+    summarize_errors: bool,
+
+    /// Only list text files with contents matching this regular expression
+    #[arg(long, value_name = "REGEX")]
+    match_content: Option<String>,
+
+    /// How much of every file `--match-content` searches
+    #[arg(
+        long,
+        value_name = "SIZE",
+        default_value = "1MiB",
+        requires = "match_content"
+    )]
+    match_content_max_bytes: bytesize::ByteSize,
+
+    /// Only list files with a size in this range, e.g. `1MB..100MB`. The
+    /// bounds are included
+    #[arg(long, value_name = "LOW..HIGH")]
+    between_size: Option<filter::SizeRange>,
+
+    /// Exclude the bounds of `--between-size`
+    #[arg(long, requires = "between_size", default_value_t = false)]
+    size_exclusive: bool,
+
+    /// List entries exactly in the order the directory walk finds them,
+    /// which is the default. Can't be combined with `--sort`
+    #[arg(long, conflicts_with = "sort", default_value_t = false)]
+    preserve_order: bool,
+
+    /// Tag directories that are mount points, i.e. on another filesystem
+    /// than their parent, with `[mount]`. Only supported on Unix
+    #[arg(long, default_value_t = false)]
+    show_mount_points: bool,
+
+    /// Print a line with the date whenever the day entries were modified
+    /// changes, in UTC. Meant to be used with `--sort modified`
+    #[arg(long, default_value_t = false)]
+    group_by_date: bool,
+
+    /// Print this line for every entry instead, with `{path}`, `{name}`,
+    /// `{size}`, `{bytes}`, `{modified}` and `{depth}` filled in, like
+    /// `find -printf`. Use `{{` and `}}` for literal braces
+    #[arg(long, value_name = "TEMPLATE")]
+    path_pattern: Option<String>,
+
+    /// Drop `.` and resolve `..` in the paths shown, without touching the
+    /// filesystem like `--absolute-paths` does
+    #[arg(long, default_value_t = false)]
+    resolve_dots: bool,
+
+    /// Show how many lines every text file has, e.g. with `--ext rs` for a
+    /// quick count of lines of code. Binary files are left blank
+    #[arg(long, default_value_t = false)]
+    count_lines: bool,
+
+    /// Behave as if stdout is a terminal even when it's piped, with colors,
+    /// `--progress`, line buffering and `--pager`, e.g. for a viewer that
+    /// understands ANSI colors
+    #[arg(long, default_value_t = false, conflicts_with = "plain")]
+    assume_tty: bool,
+
+    /// Descend into git submodules despite `--stay-in-repo`, tagging them
+    /// with `[submodule]`. Other nested repositories are still skipped
+    #[arg(long, default_value_t = false, requires = "stay_in_repo")]
+    follow_git_submodules: bool,
+
+    /// Make the output the same across runs and machines, e.g. to commit and
+    /// diff it. Entries are sorted by path, colors and `--progress` are off,
+    /// and directories get a size of 0 instead of their inode's size, which
+    /// depends on the filesystem. Times are always shown in UTC
+    #[arg(
+        long,
+        default_value_t = false,
+        conflicts_with_all = ["sort", "preserve_order", "dir_size", "show_mount_points"],
+    )]
+    deterministic: bool,
+
+    /// Put this text in front of every path written, e.g. `--include=` for
+    /// rsync. Only shows up where paths are, like with `--print0`,
+    /// `--path-pattern` or `--no-indent`, not in front of indented names
+    #[arg(long, value_name = "TEXT", allow_hyphen_values = true)]
+    prefix: Option<String>,
+
+    /// List every file or directory only once, even when it's reached through
+    /// several paths, like symlinks with `--follow-depth` or repeated lines in
+    /// `--entries-from`. Paths are compared once resolved, which costs a
+    /// lookup per entry, and symlinks that aren't followed count as entries of
+    /// their own
+    #[arg(long, default_value_t = false)]
+    merge_paths: bool,
+
+    /// Highlight entries whose name matches this glob, while still listing
+    /// everything else. Can be repeated to highlight entries matching any
+    /// of them
+    #[arg(long, value_name = "GLOB")]
+    emphasize: Vec<String>,
+
+    /// Only list files modified since the system was booted, like `--recent`
+    /// with the uptime. Only supported on Linux
+    #[arg(long, default_value_t = false, conflicts_with = "recent")]
+    since_boot: bool,
+
+    /// Tag symlinks with what they point to, like `symlink→dir`, or
+    /// `symlink→broken` if the target doesn't exist
+    #[arg(long, default_value_t = false)]
+    aggregate_symlinks: bool,
+
+    /// Indent the output of `--format json` and `--format tree-json` for
+    /// reading it yourself, instead of writing it all on one line. Doesn't
+    /// apply to `--format jsonl`, which is always one entry per line
+    #[arg(long, default_value_t = false)]
+    json_pretty: bool,
+
+    /// How many characters wide the size column is
+    #[arg(long, value_name = "N", default_value_t = format::SIZE_WIDTH)]
+    size_field_width: usize,
+
+    /// How many characters wide the modified and changed at columns are
+    #[arg(long, value_name = "N", default_value_t = format::DATE_WIDTH)]
+    modified_field_width: usize,
+
+    /// Only list symlinks whose target doesn't exist, with their paths and
+    /// where they point, and exit unsuccessfully if there are any
+    #[arg(long, default_value_t = false, conflicts_with = "following")]
+    find_broken_symlinks: bool,
+
+    /// Instead of listing entries, print how many files every user owns and
+    /// how large they are in total, the largest first. Combine with
+    /// `--deduplicate-hardlinks` to count hard linked files once
+    #[arg(long, default_value_t = false, conflicts_with = "count_by")]
+    owner_summary: bool,
+
+    /// Save the path, size and modification time of every entry listed to
+    /// this file, to compare against later with `--diff`
+    #[arg(long, value_name = "FILE")]
+    snapshot: Option<PathBuf>,
+
+    /// Instead of listing entries, show which were added (+), removed (-) or
+    /// modified (~) since the snapshot in this file was saved with
+    /// `--snapshot`
+    #[arg(long, value_name = "FILE")]
+    diff: Option<PathBuf>,
+
+    /// Write the listing to this file instead of stdout
+    #[arg(short, long, value_name = "FILE", required_if_eq("format", "parquet"))]
+    output: Option<PathBuf>,
+
+    /// Only list files modified within this long from now, 24 hours if no
+    /// duration is given. Directories are still descended into
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        num_args = 0..=1,
+        default_missing_value = "24h"
+    )]
+    recent: Option<Duration>,
+
+    /// Instead of listing entries, print how many there are and their total
+    /// size for every value of this field. Ordered by count, or by size with
+    /// `--sort size`
+    #[arg(long, value_enum, value_name = "FIELD")]
+    count_by: Option<count_by::CountBy>,
+
+    #[command(subcommand)]
+    subcommand: Option<Subcommand>,
 }
 
-fn is_hidden(entry: &DirEntry) -> bool {
-    entry
-        .file_name()
-        .to_str()
-        .map(|s| s.starts_with('.'))
-        .unwrap_or(false)
+#[derive(clap::Subcommand, Debug)]
+enum Subcommand {
+    /// Print the paths of entries whose name matches a regular expression,
+    /// like `fd`. Options for the listing go before `search`
+    Search {
+        /// Regular expression to match names against
+        pattern: String,
+    },
+    /// Instead of listing entries, find files with the same contents and how
+    /// much space they waste. Files are compared by size first, and only
+    /// those of the same size are hashed with `--hash`
+    Dupes {
+        /// Only hash the first this many bytes of files, e.g. `1MiB`, which is
+        /// faster but reports files differing only after them as duplicates
+        #[arg(long, value_name = "SIZE")]
+        hash_limit: Option<bytesize::ByteSize>,
+    },
+    /// Instead of listing entries, show how much of the total size every
+    /// entry directly within the directory takes up, the largest first, with
+    /// bars and percentages like `dust`
+    Analyze,
 }
 
-fn main() -> anyhow::Result<()> {
-    let options = Options::parse();
+/// Exit code used by `--fail-on-empty` when nothing was listed
+const EXIT_CODE_EMPTY: u8 = 2;
 
-    if options.headers {
-        let modified = if options.modified {
-            format!(
-                "{:>25}",
-                format!("\t{}\t", "Modified at".bold().underline())
-            )
+/// The line `--collapse-hidden` shows in place of the hidden entries directly
+/// within `directory`, if it has any, indented to `depth`
+fn hidden_summary(
+    directory: &Path,
+    depth: usize,
+    indent: bool,
+    size_width: usize,
+    theme: &theme::Theme,
+) -> Option<String> {
+    let hidden = std::fs::read_dir(directory)
+        .ok()?
+        .filter_map(Result::ok)
+        .filter(|entry| is_hidden_entry(&entry.file_name(), || entry.metadata().ok()))
+        .count();
+    if hidden == 0 {
+        return None;
+    }
+
+    let indent = if indent {
+        format::INDENTATION_SEQUENCE.repeat(depth.saturating_sub(1))
+    } else {
+        "".into()
+    };
+    let summary = if hidden == 1 {
+        "(+ 1 hidden entry)".to_owned()
+    } else {
+        format!("(+ {hidden} hidden entries)")
+    };
+    Some(format!(
+        "{:>size_width$}\t{}{}",
+        "",
+        theme.background.paint(&indent),
+        theme.background.paint(&summary)
+    ))
+}
+
+/// Creates a spinner on stderr counting processed entries, or a hidden one
+/// if progress isn't requested or we're not talking to a terminal. With
+/// `assume_tty`, stdout counts as a terminal either way
+fn progress_bar(enabled: bool, assume_tty: bool) -> ProgressBar {
+    let is_terminal = assume_tty || std::io::stdout().is_terminal();
+    if !enabled || !is_terminal || !std::io::stderr().is_terminal() {
+        return ProgressBar::hidden();
+    }
+
+    let progress = ProgressBar::new_spinner();
+    // SAFETY: The template is static and known to be valid
+    progress.set_style(ProgressStyle::with_template("{spinner} {pos} entries processed").unwrap());
+    progress.enable_steady_tick(Duration::from_millis(100));
+    progress
+}
+
+/// Whether `error` is from the walk not finding an entry
+fn is_not_found(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<follow::WalkError>()
+        .and_then(follow::WalkError::io_error)
+        .is_some_and(|error| error.kind() == ErrorKind::NotFound)
+}
+
+/// Runs `f`, adding the time it took to `total`
+fn timed<T>(total: &mut Duration, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    *total += start.elapsed();
+    result
+}
+
+/// Prints what the walk did to stderr for `--stats`, along with the
+/// `stat_calls` made and the time spent `formatting` while listing entries
+fn report_stats(stat_calls: usize, formatting: Duration, wall_time: Duration) {
+    let walk = explore::Stats::collected();
+    eprintln!("Entries visited:    {}", walk.entries_visited);
+    eprintln!("Stat calls:         {}", walk.stat_calls + stat_calls);
+    eprintln!("Wall time:          {wall_time:.2?}");
+    eprintln!("Reading metadata:   {:.2?}", walk.metadata_time);
+    eprintln!("Formatting entries: {formatting:.2?}");
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    match run() {
+        // Whoever reads our output, e.g. `head` or a pager, has seen enough
+        Err(error)
+            if error
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|error| error.kind() == ErrorKind::BrokenPipe) =>
+        {
+            Ok(ExitCode::SUCCESS)
+        }
+        result => result,
+    }
+}
+
+fn run() -> anyhow::Result<ExitCode> {
+    let start = Instant::now();
+    let mut options = Options::parse();
+    let subcommand = options.subcommand.take();
+    if let Some(Subcommand::Search { pattern }) = &subcommand {
+        if options.regex.is_some() {
+            anyhow::bail!(
+                "search already takes a regular expression, so --regex can't be given too"
+            );
+        }
+        options.regex = Some(pattern.clone());
+        options.no_indent = true;
+        options.path_pattern.get_or_insert_with(|| "{path}".into());
+    }
+    if options.follow {
+        options.follow_depth = Some(usize::MAX);
+    }
+    let columns = if options.columns.is_empty() {
+        Column::defaults(
+            options.long,
+            options.histogram.is_some(),
+            options.compact,
+            options.count_lines,
+            options.hash.is_some(),
+            options.modified,
+            options.ctime,
+        )
+    } else {
+        options.columns.clone()
+    };
+    // The columns need what the flags for them read or compute
+    if columns.contains(&Column::Bar) {
+        options.histogram.get_or_insert(histogram::Histogram::Size);
+    }
+    if columns.contains(&Column::Lines) {
+        options.count_lines = true;
+    }
+    if columns.contains(&Column::Hash) {
+        options.hash.get_or_insert_default();
+    }
+    if options.exec.iter().flatten().any(|arg| arg == "--dry-run") {
+        // It would run the commands for real rather than printing them
+        anyhow::bail!(
+            "--dry-run was taken as an argument of the --exec command, end the command with ';' before it"
+        );
+    }
+    let max_depth = options.max_depth.unwrap_or(usize::MAX);
+    if options.min_depth > max_depth {
+        anyhow::bail!(
+            "--min-depth {} is deeper than --max-depth {}, so nothing would be listed",
+            options.min_depth,
+            max_depth
+        );
+    }
+
+    if options.plain || options.deterministic {
+        colored::control::set_override(false);
+    } else {
+        options
+            .color
+            .apply(options.assume_tty, options.output.is_some());
+    }
+    let mut theme = theme::Theme::of(options.theme);
+    if let Some(theme_file) = &options.theme_file {
+        theme.load_file(theme_file)?;
+    }
+
+    if options.tui {
+        tui::run(
+            options.path.as_deref().unwrap_or(".".as_ref()),
+            options.hidden,
+            options.dir_size,
+            &theme,
+        )?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let paging = if options.pager {
+        output::Paging::Always
+    } else if options.no_pager || options.progress || options.exec.is_some() || options.watch {
+        output::Paging::Never
+    } else {
+        output::Paging::Auto
+    };
+    let mut output = output::Output::new(
+        paging,
+        options.output.as_deref(),
+        options.line_buffered,
+        options.assume_tty,
+    )?;
+
+    if options.export_schema {
+        let schema = schemars::schema_for!(Vec<record::Record>);
+        serde_json::to_writer_pretty(&mut output, &schema)?;
+        writeln!(output)?;
+        output.finish()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(size_of) = &options.size_of {
+        let size = du::total_size(size_of, options.deduplicate_hardlinks)?;
+        writeln!(output, "{}", bytesize::ByteSize(size))?;
+        output.finish()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(sums_file) = &options.checksum_verify {
+        let root = options.path.as_deref().unwrap_or(".".as_ref());
+        let all_ok = verify::verify(
+            sums_file,
+            root,
+            options.hash.unwrap_or_default(),
+            &mut output,
+            &theme,
+        )?;
+        output.finish()?;
+        return Ok(if all_ok {
+            ExitCode::SUCCESS
         } else {
-            "".into()
-        };
+            ExitCode::FAILURE
+        });
+    }
+
+    if let Some(Subcommand::Dupes { hash_limit }) = subcommand {
+        let progress = progress_bar(options.progress, options.assume_tty);
+        let mut files = Vec::new();
+        let mut seen_files = HashSet::new();
+        for entry in Explorer::new(options.path.as_deref().unwrap_or(".".as_ref()))
+            .min_depth(options.min_depth)
+            .max_depth(max_depth)
+            .hidden(options.hidden)
+            .walk()
+        {
+            progress.inc(1);
+            match entry {
+                // Hard links share their contents rather than wasting space
+                Ok(entry)
+                    if entry.metadata.is_file()
+                        && du::is_first_link(&entry.metadata, &mut seen_files) =>
+                {
+                    files.push((entry.path, entry.size))
+                }
+                Ok(_) => {}
+                Err(error) => progress.suspend(|| {
+                    eprintln!("{} {error:#}", "warning:".yellow().bold());
+                }),
+            }
+        }
 
-        println!(
-            "{:>5}{}{}\t{:>11}{}",
-            "",
-            "Size".bold().underline(),
-            modified,
-            "",
-            "Name".bold().underline()
+        let sets = dupes::find(
+            files,
+            options.hash.unwrap_or_default(),
+            hash_limit.map(|limit| limit.as_u64()),
+            &progress,
         );
+        progress.finish_and_clear();
+        dupes::write(&sets, &mut output, &theme)?;
+        output.finish()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(Subcommand::Analyze) = subcommand {
+        let root = options.path.as_deref().unwrap_or(".".as_ref());
+        let analysis =
+            analyze::Analysis::compute(root, options.hidden, options.deduplicate_hardlinks)?;
+        analysis.write(&mut output, options.limit, &theme)?;
+        output.finish()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if options.watch {
+        let root = options.path.as_deref().unwrap_or(".".as_ref());
+        let absolute_root = root
+            .canonicalize()
+            .context(format!("Failed resolving {}", root.display()))?;
+        let path_filter =
+            filter::PathFilter::new(&options.include, &options.exclude, options.ignore_case)?;
+        let name_filter = filter::NameFilter::new(
+            &options.glob,
+            options.regex.as_deref(),
+            &options.ext,
+            &options.exclude_ext,
+            options.ignore_case,
+        )?;
+        let watch = watch::Watch {
+            root,
+            absolute_root: &absolute_root,
+            min_depth: options.min_depth,
+            max_depth,
+            hidden: options.hidden,
+            path_filter: &path_filter,
+            name_filter: &name_filter,
+            theme: &theme,
+        };
+        watch.run(&mut output)?;
+        output.finish()?;
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    match options.format {
+        output::Format::Tsv => writeln!(output, "{}", record::COLUMNS.join("\t"))?,
+        output::Format::Csv => writeln!(output, "{}", record::COLUMNS.join(","))?,
+        _ => {}
     }
 
-    for entry in WalkDir::new(options.path.unwrap_or(".".into()))
+    let mut headers = (options.headers
+        && !options.print0
+        && options.path_pattern.is_none()
+        && options.diff.is_none()
+        && options.format == output::Format::Text)
+        .then(|| {
+            // Right-aligns `header` in a column `width` wide, padding before
+            // painting so the padding isn't underlined
+            let column = |header: &str, width: usize| {
+                let padding = " ".repeat(width.saturating_sub(header.chars().count()));
+                format!("{padding}{}", theme.header.paint(header))
+            };
+            let date_width = options.modified_field_width;
+            if options.newest_in_dir {
+                return format!(
+                    "{}\t{}\t{}",
+                    column("Modified at", date_width),
+                    theme.header.paint("Name"),
+                    theme.header.paint("Newest file")
+                );
+            }
+
+            let hash_width = options.hash.unwrap_or_default().hex_len();
+            let headers: Vec<_> = columns
+                .iter()
+                .map(|header| match header {
+                    Column::Permissions => long::LongColumns::headers(&theme),
+                    Column::Size => column("Size", options.size_field_width),
+                    Column::Bar => column("", histogram::WIDTH),
+                    Column::Entries => column(ENTRIES_HEADER, ENTRIES_HEADER.len()),
+                    Column::Lines => column(LINES_HEADER, LINES_HEADER.len()),
+                    Column::Hash => column("Hash", hash_width),
+                    Column::Modified => column("Modified at", date_width),
+                    Column::Changed => column("Changed at", date_width),
+                    Column::Name => theme.header.paint("Name").to_string(),
+                })
+                .collect();
+            headers.join("\t")
+        });
+    if !options.no_headers_when_empty {
+        if let Some(headers) = headers.take() {
+            writeln!(output, "{headers}")?;
+        }
+    }
+
+    let path = options.path.unwrap_or(".".into());
+
+    let changed_paths = options
+        .since_commit
+        .as_deref()
+        .map(|rev| git::ChangedPaths::since(&path, rev))
+        .transpose()?;
+    let absolute_root = path
+        .canonicalize()
+        .context(format!("Failed resolving {}", path.display()))?;
+    let submodules = options
+        .follow_git_submodules
+        .then(|| git::submodule_paths(&path))
+        .transpose()?;
+
+    let mut size_cache = options
+        .cache
+        .as_deref()
+        .map(cache::SizeCache::load)
+        .transpose()?;
+    let listed_paths = options
+        .entries_from
+        .as_deref()
+        .map(|file| -> anyhow::Result<Vec<PathBuf>> {
+            let paths = std::fs::read_to_string(file)
+                .context(format!("Failed reading {}", file.display()))?;
+            Ok(paths
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect())
+        })
+        .transpose()?;
+
+    let directory_sizes = (options.du || options.compact)
+        .then(|| match &listed_paths {
+            Some(paths) => du::DirectorySizes::compute_each(paths, options.deduplicate_hardlinks),
+            None => du::DirectorySizes::compute(
+                &path,
+                &absolute_root,
+                size_cache.as_mut(),
+                options.deduplicate_hardlinks,
+            ),
+        })
+        .transpose()?;
+    if let (Some(size_cache), Some(file)) = (&size_cache, &options.cache) {
+        size_cache.save(file)?;
+    }
+    let newest_modifications = (options.stale.is_some() || options.newest_in_dir)
+        .then(|| newest::NewestModifications::compute(&path))
+        .transpose()?;
+    let recent_since = if options.since_boot {
+        Some(boot::boot_time()?)
+    } else {
+        options
+            .recent
+            .map(|recent| SystemTime::now().checked_sub(recent).unwrap_or(UNIX_EPOCH))
+    };
+    let stale_before = options
+        .stale
+        .map(|stale| SystemTime::now().checked_sub(stale).unwrap_or(UNIX_EPOCH));
+
+    let path_filter =
+        filter::PathFilter::new(&options.include, &options.exclude, options.ignore_case)?;
+    let name_filter = filter::NameFilter::new(
+        &options.glob,
+        options.regex.as_deref(),
+        &options.ext,
+        &options.exclude_ext,
+        options.ignore_case,
+    )?;
+    let emphasized = (!options.emphasize.is_empty())
+        .then(|| filter::NameFilter::new(&options.emphasize, None, &[], &[], options.ignore_case))
+        .transpose()?;
+    let content_matcher = options
+        .match_content
+        .as_deref()
+        .map(|regex| content::ContentMatcher::new(regex, options.match_content_max_bytes.as_u64()))
+        .transpose()?;
+    let relative_base = options.relative_to.as_deref().map(paths::resolved_path);
+    let path_pattern = options
+        .path_pattern
+        .as_deref()
+        .map(template::Template::parse)
+        .transpose()?;
+
+    let command = options.exec.map(exec::CommandTemplate::new);
+
+    // The progress would be drawn on top of the pager
+    let progress = progress_bar(
+        options.progress && !output.is_pager() && !options.deterministic,
+        options.assume_tty,
+    );
+    // Resolved paths listed so far, with `--merge-paths`
+    let mut seen_paths = options.merge_paths.then(HashSet::new);
+    let mut listed_entries = 0usize;
+    // The size of the files listed so far, for `--max-total-size`
+    let mut total_size = 0u64;
+    // The size of everything listed, which is the total with `--entries-from`
+    let mut listed_size = 0u64;
+    let mut error_summary = errors::ErrorSummary::default();
+    // The day the previous entry was modified, for `--group-by-date`
+    let mut previous_day = None;
+    let mut failed_commands = 0usize;
+    // What listing the entries took on top of the walk, for `--stats`
+    let mut stat_calls = 0usize;
+    let mut formatting_time = Duration::ZERO;
+    let mut records = Vec::new();
+    let mut tree = tree_json::TreeBuilder::default();
+    let mut parquet = parquet_file::ParquetBuilder::default();
+    let mut groups = options
+        .count_by
+        .or(options.owner_summary.then_some(count_by::CountBy::Owner))
+        .map(count_by::Groups::new);
+    let previous_snapshot = options
+        .diff
+        .as_deref()
+        .map(snapshot::Snapshot::load)
+        .transpose()?;
+    let mut snapshot =
+        (options.snapshot.is_some() || options.diff.is_some()).then(snapshot::Snapshot::default);
+    let long_columns = long::LongColumns::default();
+    let mut summary = summary::Summary::new(options.deduplicate_hardlinks);
+    // Files counted by `--owner-summary`, to count every hard linked file once
+    let mut seen_files = HashSet::new();
+
+    // Directories below the root that are repositories of their own, whose
+    // contents `--stay-in-repo` prunes
+    let nested_repositories = Mutex::new(HashSet::new());
+
+    let needs_children =
+        columns.contains(&Column::Entries) || matches!(options.sort, Some(sort::SortKey::Children));
+    let mut explorer = Explorer::new(&path)
         .min_depth(options.min_depth)
-        .max_depth(options.max_depth)
-        .into_iter()
-        .filter_entry(|entry| options.hidden || !is_hidden(entry))
-    {
-        let entry = entry.context("Error getting file entry")?;
-        let metadata = entry.metadata().context(format!(
-            "Failed extracting metadata for {}. Perhaps you are missing permissions?",
-            entry.path().display()
-        ))?;
-        let modified_at = metadata
-            .modified()
-            .context("Could not get date modified for the entry")?;
-        let size = FormatSize(metadata);
-        let formatted_entry = FormatEntry(&entry);
-
-        let formatted_date = if options.modified {
-            let modified = FormatModifiedAt(modified_at);
-            format!("{:>25}\t", modified)
+        .max_depth(max_depth)
+        .hidden(options.hidden && !options.collapse_hidden)
+        // Sorting every directory's entries by name keeps the tree order
+        .sort_by_file_name(options.deterministic)
+        .gitignore(options.gitignore)
+        .path_filter(path_filter)
+        .name_filter(name_filter)
+        .types(options.only_type.clone())
+        .directories_only(options.compact || options.stale.is_some() || options.newest_in_dir)
+        .name_length(
+            options.min_name_length,
+            options.max_name_length,
+            options.name_length_bytes,
+        )
+        .file_size(
+            options.min_size.map(|min| min.as_u64()),
+            options.max_size.map(|max| max.as_u64()),
+        )
+        .modified(
+            options.newer_than.map(|newer| newer.0),
+            options.older_than.map(|older| older.0),
+        )
+        .broken_symlinks_only(options.find_broken_symlinks)
+        .threads(options.threads)
+        .unordered(options.unordered)
+        .count_children(needs_children)
+        .count_lines(options.count_lines)
+        .hash(options.hash)
+        .resolve_targets_size(options.resolve_targets_size)
+        .filter_entry(|entry| {
+            changed_paths.as_ref().is_none_or(|changed| {
+                changed.contains(&absolute_entry_path(entry.path, &path, &absolute_root))
+            })
+        })
+        .on_warning(|warning| {
+            progress.suspend(|| eprintln!("{} {warning}", "warning:".yellow().bold()))
+        });
+    if let Some(follow_depth) = options.follow_depth {
+        explorer = explorer.follow_links(follow_depth);
+    }
+    if let Some(matcher) = content_matcher {
+        explorer = explorer.content(matcher);
+    }
+    // Listed paths aren't walked into, but go through the same filters as
+    // what's found below the root
+    if let Some(paths) = &listed_paths {
+        explorer = explorer.paths(paths.clone());
+    }
+    if let Some(sizes) = &directory_sizes {
+        explorer = explorer.directory_sizes(sizes);
+    }
+    if let Some(range) = options.between_size {
+        explorer = explorer.file_size_range(range, options.size_exclusive);
+    }
+    if let Some(min_count) = options.min_count {
+        explorer = explorer.min_children(min_count);
+    }
+    if let Some(since) = recent_since {
+        explorer = explorer.modified_since(since);
+    }
+    if let Some(newest) = &newest_modifications {
+        if options.newest_in_dir {
+            explorer = explorer.newest_in_dir(newest);
+        }
+        if let Some(before) = stale_before {
+            explorer = explorer.stale(newest, before);
+        }
+    }
+    if options.stay_in_repo {
+        explorer = explorer.filter_entry(|entry| {
+            // SAFETY: Nothing panics while holding the lock
+            let mut nested_repositories = nested_repositories.lock().unwrap();
+            if entry
+                .path
+                .parent()
+                .is_some_and(|parent| nested_repositories.contains(parent))
+            {
+                return false;
+            }
+            let is_submodule = || {
+                submodules.as_ref().is_some_and(|submodules| {
+                    submodules.contains(&absolute_entry_path(entry.path, &path, &absolute_root))
+                })
+            };
+            if entry.depth > 0
+                && entry.is_dir
+                && git::is_repository_root(entry.path)
+                && !is_submodule()
+            {
+                nested_repositories.insert(entry.path.to_path_buf());
+            }
+            true
+        });
+    }
+
+    let walk = explorer
+        .walk()
+        .filter(|entry| {
+            let Some(error) = entry
+                .as_ref()
+                .err()
+                .and_then(|error| error.downcast_ref::<follow::WalkError>())
+            else {
+                return true;
+            };
+            let (Some(link), Some(ancestor)) = (error.path(), error.loop_ancestor()) else {
+                return true;
+            };
+            if options.fail_on_symlink_loop {
+                return true;
+            }
+            progress.suspend(|| {
+                eprintln!(
+                    "{} {} points back to its ancestor {}",
+                    "symlink loop:".yellow().bold(),
+                    link.display(),
+                    ancestor.display()
+                )
+            });
+            false
+        })
+        .inspect(|_| progress.inc(1))
+        .map(|entry| {
+            let mut entry = entry?;
+            if options.deterministic && entry.metadata.is_dir() && directory_sizes.is_none() {
+                entry.size = 0;
+            }
+            Ok(entry)
+        });
+
+    // The size of the largest file listed, which `--histogram` scales bars to
+    let mut largest_file = 0;
+
+    let entries: Box<dyn Iterator<Item = anyhow::Result<Entry>>> =
+        if options.flatten || options.sort.is_some() || options.histogram.is_some() || options.tree
+        {
+            let mut entries = Vec::new();
+            // Skipped errors are reported before the sorted entries
+            let mut errors = Vec::new();
+            for entry in walk {
+                match entry {
+                    Ok(entry) => entries.push(entry),
+                    Err(error) if options.skip_errors => errors.push(error),
+                    Err(error) => return Err(error),
+                }
+            }
+            if options.flatten {
+                entries = entry::flatten_directory_chains(entries);
+            }
+            if let Some(key) = options.sort {
+                sort::sort(&mut entries, key, options.seed, directory_sizes.is_some());
+                if options.reverse {
+                    entries.reverse();
+                }
+            }
+            if let Some(limit) = options.limit {
+                entries.truncate(limit);
+            }
+            if options.tree {
+                entry::draw_tree(&mut entries);
+            }
+            largest_file = entries
+                .iter()
+                .filter(|entry| !entry.metadata.is_dir())
+                .map(|entry| entry.size)
+                .max()
+                .unwrap_or(0);
+            Box::new(
+                errors
+                    .into_iter()
+                    .map(Err)
+                    .chain(entries.into_iter().map(Ok)),
+            )
         } else {
-            "".into()
+            Box::new(walk)
         };
-        println!("{}\t{}{}", size, formatted_date, formatted_entry);
+
+    for entry in entries.take(options.limit.unwrap_or(usize::MAX)) {
+        let mut entry = match entry {
+            Ok(entry) => entry,
+            Err(error) if options.format == output::Format::TreeJson => {
+                // Keep unreadable entries as leaves rather than giving up on the whole tree
+                let Some(walk_error) = error.downcast_ref::<follow::WalkError>() else {
+                    return Err(error);
+                };
+                let Some(path) = walk_error.path() else {
+                    return Err(error);
+                };
+                let message = match walk_error.io_error() {
+                    Some(io_error) => io_error.to_string(),
+                    None => walk_error.to_string(),
+                };
+                tree.add_error(path.to_path_buf(), message);
+                continue;
+            }
+            Err(error) if listed_paths.is_some() && is_not_found(&error) => {
+                // SAFETY: `is_not_found` only holds for walk errors
+                let path = error.downcast_ref::<follow::WalkError>().unwrap().path();
+                if let (Some(path), output::Format::Text) = (path, options.format) {
+                    let missing = format!(
+                        "{:>width$}\t{} (missing)",
+                        "",
+                        path.display(),
+                        width = options.size_field_width
+                    );
+                    progress
+                        .suspend(|| writeln!(output, "{}", theme.background.paint(&missing)))?;
+                }
+                continue;
+            }
+            Err(error) if options.skip_errors => {
+                if options.summarize_errors {
+                    error_summary.add(&error);
+                } else {
+                    let message = match errors::describe(&error) {
+                        (kind, Some(path)) => format!("{}: {kind}", path.display()),
+                        (_, None) => format!("{error:#}"),
+                    };
+                    progress.suspend(|| eprintln!("{} {message}", "warning:".yellow().bold()));
+                }
+                continue;
+            }
+            Err(error) => return Err(error),
+        };
+        if let Some(seen_paths) = &mut seen_paths {
+            // A followed symlink is the same entry as what it points to, but
+            // one that's listed as a link isn't
+            let followed = entry.link_target.is_some() && !entry.metadata.is_symlink();
+            let path = if followed {
+                paths::resolved_path(&entry.path)
+            } else {
+                absolute_path(&entry.path)
+            };
+            if !seen_paths.insert(path) {
+                continue;
+            }
+        }
+        if let Some(budget) = options.max_total_size {
+            if !entry.metadata.is_dir() {
+                if total_size + entry.size > budget.as_u64() {
+                    progress.suspend(|| {
+                        eprintln!(
+                            "Stopped after {listed_entries} entries totaling {}, as the next would exceed --max-total-size {budget}",
+                            bytesize::ByteSize(total_size)
+                        )
+                    });
+                    break;
+                }
+                total_size += entry.size;
+            }
+        }
+        let walk_path = entry.path.clone();
+        listed_entries += 1;
+        listed_size += entry.size;
+        summary.add(&entry);
+
+        if options.absolute_paths {
+            entry.path = absolute_path(&entry.path);
+        }
+
+        if let Some(snapshot) = &mut snapshot {
+            snapshot.add(walk_path.strip_prefix(&path).unwrap_or(&walk_path), &entry);
+            if options.diff.is_some() {
+                continue;
+            }
+        }
+
+        if let Some(command) = &command {
+            if options.dry_run {
+                let command_line = exec::FormatCommandLine(&command.substitute(&entry.path));
+                progress.suspend(|| writeln!(output, "{command_line}"))?;
+            } else if !progress.suspend(|| command.run(&entry.path))?.success() {
+                failed_commands += 1;
+            }
+            continue;
+        }
+
+        if let Some(base) = &relative_base {
+            let absolute = absolute_entry_path(&entry.path, &path, &absolute_root);
+            entry.path = relative_to(&absolute, base);
+        } else if options.no_indent && !options.absolute_paths {
+            entry.path = relative_to(&entry.path, &path);
+        }
+        if options.resolve_dots {
+            entry.path = resolve_dots(&entry.path);
+        }
+        if let Some(prefix) = &options.prefix {
+            let mut prefixed = OsString::from(prefix);
+            prefixed.push(&entry.path);
+            entry.path = prefixed.into();
+        }
+
+        if options.print0 {
+            progress.suspend(|| -> std::io::Result<()> {
+                output.write_all(entry.path.as_os_str().as_encoded_bytes())?;
+                output.write_all(b"\0")?;
+                // NUL terminated entries don't trigger line buffering
+                if options.line_buffered {
+                    output.flush()?;
+                }
+                Ok(())
+            })?;
+            continue;
+        }
+
+        if let Some(path_pattern) = &path_pattern {
+            let line = path_pattern.render(&entry);
+            progress.suspend(|| writeln!(output, "{line}"))?;
+            continue;
+        }
+
+        if let Some(groups) = &mut groups {
+            let is_counted = !options.owner_summary
+                || (!entry.metadata.is_dir()
+                    && (!options.deduplicate_hardlinks
+                        || du::is_first_link(&entry.metadata, &mut seen_files)));
+            if is_counted {
+                groups.add(&entry);
+            }
+            continue;
+        }
+
+        match options.format {
+            output::Format::Text => {}
+            output::Format::Tsv => {
+                record::Record::from(&entry).write_tsv(&mut output)?;
+                continue;
+            }
+            output::Format::Csv => {
+                record::Record::from(&entry).write_csv(&mut output)?;
+                continue;
+            }
+            output::Format::Jsonl => {
+                serde_json::to_writer(&mut output, &record::Record::from(&entry))?;
+                writeln!(output)?;
+                continue;
+            }
+            output::Format::TreeJson => {
+                tree.add_entry(walk_path, record::Record::from(&entry));
+                continue;
+            }
+            output::Format::Json
+            | output::Format::Yaml
+            | output::Format::Toml
+            | output::Format::Html => {
+                records.push(record::Record::from(&entry));
+                continue;
+            }
+            output::Format::Parquet => {
+                parquet.add(&entry);
+                continue;
+            }
+        }
+
+        let date_width = options.modified_field_width;
+        let show_path = options.find_broken_symlinks
+            || options.sort.is_some()
+            || options.absolute_paths
+            || relative_base.is_some()
+            || listed_paths.is_some()
+            || options.no_indent;
+        let line = timed(&mut formatting_time, || -> anyhow::Result<String> {
+            let modified_at = entry
+                .metadata
+                .modified()
+                .context("Could not get date modified for the entry")?;
+            let size = FormatSize {
+                size: entry.size,
+                directory: (entry.metadata.is_dir() && directory_sizes.is_none())
+                    .then_some(options.dir_size),
+                width: options.size_field_width,
+                theme: &theme,
+            };
+            let formatted_entry = FormatEntry {
+                entry: &entry,
+                show_path,
+                indent: !options.plain,
+                emphasized: emphasized.as_ref().is_some_and(|emphasized| {
+                    emphasized.matches(&entry.name, entry.metadata.is_dir())
+                }),
+                icons: options.icons,
+                theme: &theme,
+            };
+
+            if let Some((file, modified)) = &entry.newest_file {
+                let file = file.strip_prefix(&walk_path).unwrap_or(file);
+                return Ok(format!(
+                    "{:>date_width$}\t{}\t{}",
+                    FormatModifiedAt(*modified, &theme),
+                    formatted_entry,
+                    file.display()
+                ));
+            }
+            let mut annotations = if options.anomalies && entry.modified_before_created() {
+                format!(" {}", theme.anomaly.paint("(modified before created)"))
+            } else {
+                "".into()
+            };
+            if submodules.as_ref().is_some_and(|submodules| {
+                submodules.contains(&absolute_entry_path(&walk_path, &path, &absolute_root))
+            }) {
+                annotations.push_str(&format!(" {}", theme.tag.paint("[submodule]")));
+            }
+            if options.aggregate_symlinks && entry.metadata.file_type().is_symlink() {
+                stat_calls += 1;
+                let target = match std::fs::metadata(&walk_path) {
+                    Ok(target) => record::kind(target.file_type()),
+                    Err(_) => "broken",
+                };
+                annotations.push_str(&format!(
+                    " {}",
+                    theme.tag.paint(&format!("symlink→{target}"))
+                ));
+            }
+            if let Some(target) = entry
+                .link_target
+                .as_ref()
+                .filter(|_| !options.plain || options.find_broken_symlinks)
+            {
+                annotations.push_str(&format!(" -> {}", target.display()));
+            }
+            if options.show_mount_points && entry::is_mount_point(&walk_path, &entry.metadata) {
+                annotations.push_str(&format!(" {}", theme.tag.paint("[mount]")));
+            }
+            let hash_width = options.hash.unwrap_or_default().hex_len();
+            let entries_width = ENTRIES_HEADER.len();
+            let lines_width = LINES_HEADER.len();
+            let cells: Vec<_> = columns
+                .iter()
+                .map(|column| match column {
+                    Column::Permissions => long_columns.format(&entry.metadata),
+                    Column::Size => size.to_string(),
+                    Column::Bar if entry.metadata.is_dir() => " ".repeat(histogram::WIDTH),
+                    Column::Bar => histogram::SizeBar {
+                        size: entry.size,
+                        largest: largest_file,
+                        theme: &theme,
+                    }
+                    .to_string(),
+                    Column::Entries => match entry.children {
+                        Some(children) => format!("{children:>entries_width$}"),
+                        None => format!("{:>entries_width$}", ""),
+                    },
+                    Column::Lines => match entry.lines {
+                        Some(lines) => format!("{lines:>lines_width$}"),
+                        None => format!("{:>lines_width$}", ""),
+                    },
+                    Column::Hash => match &entry.hash {
+                        Some(hash) => format!("{hash:>hash_width$}"),
+                        None => format!("{:>hash_width$}", "-"),
+                    },
+                    Column::Modified => {
+                        format!("{:>date_width$}", FormatModifiedAt(modified_at, &theme))
+                    }
+                    Column::Changed => match entry.changed() {
+                        Some(changed) => {
+                            format!("{:>date_width$}", FormatModifiedAt(changed, &theme))
+                        }
+                        None => format!("{:>date_width$}", ""),
+                    },
+                    Column::Name => format!("{formatted_entry}{annotations}"),
+                })
+                .collect();
+            Ok(cells.join("\t"))
+        })?;
+        let day = options
+            .group_by_date
+            .then(|| entry.metadata.modified().ok())
+            .flatten()
+            .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).date_naive());
+        let day_separator = day.filter(|day| previous_day != Some(*day)).map(|day| {
+            previous_day = Some(day);
+            theme
+                .background
+                .paint(&format!("── {} ──", day.format("%Y-%m-%d")))
+        });
+        progress.suspend(|| -> std::io::Result<()> {
+            if let Some(headers) = headers.take() {
+                writeln!(output, "{headers}")?;
+            }
+            if let Some(day_separator) = day_separator {
+                writeln!(output, "{day_separator}")?;
+            }
+            writeln!(output, "{line}")
+        })?;
+
+        let descends = entry.metadata.is_dir() && entry.depth < max_depth;
+        if options.collapse_hidden && descends {
+            let indent = !options.plain && !show_path;
+            if let Some(summary) = hidden_summary(
+                &walk_path,
+                entry.depth + 1,
+                indent,
+                options.size_field_width,
+                &theme,
+            ) {
+                progress.suspend(|| writeln!(output, "{summary}"))?;
+            }
+        }
     }
 
-    Ok(())
-}
+    progress.finish_and_clear();
 
-struct FormatEntry<'walk_dir_loop>(&'walk_dir_loop DirEntry);
-
-impl<'walk_dir_loop> Display for FormatEntry<'walk_dir_loop> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let path = self.0.path();
-        // SAFETY: We can safely unwrap here as we know the path contains at least one part (e.g. `.` or `./thing`, or so on)
-        let name = path.iter().last().unwrap().to_string_lossy();
-        let formatted_entry = if path.is_file() {
-            name.white()
-        } else if path.is_dir() {
-            name.blue()
-        } else {
-            // We'll assume symlinks
-            name.yellow()
+    // The root isn't listed itself, so its summary goes at the end
+    let is_root_listed = options.min_depth == 0;
+    if options.collapse_hidden
+        && options.format == output::Format::Text
+        && !is_root_listed
+        && max_depth > 0
+    {
+        if let Some(summary) = hidden_summary(&path, 1, false, options.size_field_width, &theme) {
+            if let Some(headers) = headers.take() {
+                writeln!(output, "{headers}")?;
+            }
+            writeln!(output, "{summary}")?;
+        }
+    }
+
+    let is_listing = options.format == output::Format::Text
+        && !options.print0
+        && path_pattern.is_none()
+        && groups.is_none()
+        && command.is_none()
+        && options.diff.is_none();
+    let total = match &listed_paths {
+        Some(_) => Some(listed_size),
+        None => directory_sizes.as_ref().and_then(|sizes| sizes.get(&path)),
+    };
+    if let Some(total) = total.filter(|_| options.du && is_listing) {
+        let total = FormatSize {
+            size: total,
+            directory: None,
+            width: options.size_field_width,
+            theme: &theme,
         };
+        writeln!(output, "{total}\t{}", theme.header.paint("Total"))?;
+    }
 
-        const INDENTATION_SEQUENCE: &str = "⤷ ";
+    if options.summary && is_listing {
+        let summary = summary::FormatSummary {
+            summary: &summary,
+            theme: &theme,
+        };
+        writeln!(output, "{summary}")?;
+    }
 
-        let indent = INDENTATION_SEQUENCE
-            .repeat(self.0.depth().saturating_sub(1))
-            .dimmed();
+    if let (Some(snapshot), Some(previous)) = (&snapshot, &previous_snapshot) {
+        snapshot.diff(previous, &mut output, &theme)?;
+    }
+    if let (Some(snapshot), Some(file)) = (&snapshot, &options.snapshot) {
+        snapshot.save(file)?;
+    }
 
-        f.write_fmt(format_args!("{indent}{formatted_entry}"))
+    if let Some(groups) = groups {
+        let by_size = options.owner_summary || matches!(options.sort, Some(sort::SortKey::Size));
+        groups.write(&mut output, by_size, &theme)?;
     }
-}
 
-struct FormatSize(Metadata);
+    match options.format {
+        output::Format::Text
+        | output::Format::Tsv
+        | output::Format::Csv
+        | output::Format::Jsonl => {}
+        output::Format::Json => {
+            if options.json_pretty {
+                serde_json::to_writer_pretty(&mut output, &records)?;
+            } else {
+                serde_json::to_writer(&mut output, &records)?;
+            }
+            writeln!(output)?;
+        }
+        output::Format::Yaml => serde_yaml::to_writer(&mut output, &records)?,
+        output::Format::Toml => {
+            // TOML documents are tables, so the entries can't be the whole document
+            #[derive(serde::Serialize)]
+            struct Document<'a> {
+                entries: &'a [record::Record],
+            }
+            let document = toml::to_string(&Document { entries: &records })?;
+            output.write_all(document.as_bytes())?;
+        }
+        output::Format::Html => html::write(&records, &path, &mut output)?,
+        output::Format::TreeJson => {
+            let root = record::Record::from(&Entry::at(&path)?);
+            let tree = tree.finish(&path, root);
+            if options.json_pretty {
+                serde_json::to_writer_pretty(&mut output, &tree)?;
+            } else {
+                serde_json::to_writer(&mut output, &tree)?;
+            }
+            writeln!(output)?;
+        }
+        output::Format::Parquet => {
+            // The Parquet writer needs to be able to move between threads,
+            // which the locked stdout can't
+            let mut file = Vec::new();
+            parquet.finish(&mut file)?;
+            output.write_all(&file)?;
+        }
+    }
+    output.finish()?;
 
-impl Display for FormatSize {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!("{:>9}", ByteSize(self.0.len())).green())
+    if options.stats {
+        report_stats(stat_calls, formatting_time, start.elapsed());
     }
-}
+    error_summary.report(&theme);
 
-struct FormatModifiedAt(SystemTime);
+    if failed_commands > 0 {
+        eprintln!("{failed_commands} command(s) exited unsuccessfully");
+        return Ok(ExitCode::FAILURE);
+    }
 
-impl Display for FormatModifiedAt {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let date = chrono::DateTime::<chrono::Utc>::from(self.0);
-        f.write_fmt(format_args!(
-            "{}",
-            date.to_rfc2822()
-                // SAFETY: We know it is in UTC so the stripping always works, probably 🤠
-                .strip_suffix(" +0000")
-                .unwrap()
-                .blue()
-        ))
+    if options.find_broken_symlinks && listed_entries > 0 {
+        eprintln!("{listed_entries} broken symlink(s) found");
+        return Ok(ExitCode::FAILURE);
     }
+
+    if options.fail_on_empty && listed_entries == 0 {
+        return Ok(ExitCode::from(EXIT_CODE_EMPTY));
+    }
+
+    Ok(ExitCode::SUCCESS)
 }
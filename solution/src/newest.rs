@@ -0,0 +1,109 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use walkdir::WalkDir;
+
+use crate::stats;
+
+/// What within a directory was modified most recently
+struct Newest {
+    /// When anything within was last modified, the directory itself included
+    modified: SystemTime,
+    /// The most recently modified file within, if there are any
+    file: Option<(PathBuf, SystemTime)>,
+}
+
+impl Newest {
+    fn merge(&mut self, other: &Newest) {
+        self.modified = self.modified.max(other.modified);
+        if let Some((path, modified)) = &other.file {
+            self.merge_file(path, *modified);
+        }
+    }
+
+    fn merge_file(&mut self, path: &Path, modified: SystemTime) {
+        self.modified = self.modified.max(modified);
+        if self
+            .file
+            .as_ref()
+            .is_none_or(|(_, newest)| modified > *newest)
+        {
+            self.file = Some((path.to_path_buf(), modified));
+        }
+    }
+}
+
+/// When anything within every directory below a root was last modified, and
+/// which file within was modified last
+pub struct NewestModifications(HashMap<PathBuf, Newest>);
+
+impl NewestModifications {
+    /// Walks everything below `root`, carrying the newest modification time
+    /// bottom-up into the ancestor directories. A directory's own modification
+    /// time counts too, as it changes when entries are added or removed
+    pub fn compute(root: &Path) -> anyhow::Result<Self> {
+        let mut newest = HashMap::new();
+        // The directories leading to the current entry, with the newest times so far
+        let mut ancestors: Vec<(PathBuf, Newest)> = Vec::new();
+
+        for entry in WalkDir::new(root) {
+            let entry = entry.context("Error getting file entry")?;
+
+            while ancestors.len() > entry.depth() {
+                // SAFETY: We just checked that there is an ancestor to pop
+                let (directory, directory_newest) = ancestors.pop().unwrap();
+                if let Some((_, parent_newest)) = ancestors.last_mut() {
+                    parent_newest.merge(&directory_newest);
+                }
+                newest.insert(directory, directory_newest);
+            }
+
+            let modified = stats::stat_call(|| entry.metadata())
+                .context(format!(
+                    "Failed extracting metadata for {}. Perhaps you are missing permissions?",
+                    entry.path().display()
+                ))?
+                .modified()
+                .context(format!(
+                    "Could not get date modified for {}",
+                    entry.path().display()
+                ))?;
+
+            if entry.file_type().is_dir() {
+                let directory_newest = Newest {
+                    modified,
+                    file: None,
+                };
+                ancestors.push((entry.into_path(), directory_newest));
+            } else if let Some((_, parent_newest)) = ancestors.last_mut() {
+                parent_newest.merge_file(entry.path(), modified);
+            }
+        }
+
+        while let Some((directory, directory_newest)) = ancestors.pop() {
+            if let Some((_, parent_newest)) = ancestors.last_mut() {
+                parent_newest.merge(&directory_newest);
+            }
+            newest.insert(directory, directory_newest);
+        }
+
+        Ok(Self(newest))
+    }
+
+    /// When anything within the directory at `path` was last modified, if it
+    /// was below the root
+    pub fn modified(&self, path: &Path) -> Option<SystemTime> {
+        self.0.get(path).map(|newest| newest.modified)
+    }
+
+    /// The most recently modified file within the directory at `path`, and
+    /// when it was modified
+    pub fn file(&self, path: &Path) -> Option<(&Path, SystemTime)> {
+        let (file, modified) = self.0.get(path)?.file.as_ref()?;
+        Some((file, *modified))
+    }
+}
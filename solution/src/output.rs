@@ -0,0 +1,291 @@
+use std::{
+    fs::File,
+    io::{BufWriter, IsTerminal, StdoutLock, Write},
+    path::Path,
+    process::{Child, ChildStdin, Command, Stdio},
+};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use colored::Colorize;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    /// Colored columns for reading in a terminal
+    Text,
+    /// A JSON array of entries
+    Json,
+    /// A YAML sequence of entries
+    Yaml,
+    /// A nested JSON object, with the entries of each directory in `children`
+    TreeJson,
+    /// Tab-separated path, size in bytes, modification time and kind, one
+    /// entry per line after a header
+    Tsv,
+    /// Comma-separated path, size in bytes, modification time and kind, one
+    /// entry per line after a header
+    Csv,
+    /// A Parquet file with path, size, mtime and is_dir columns, for loading
+    /// into e.g. pandas or Polars. Needs `--output`
+    Parquet,
+    /// A TOML document with an `entries` array of tables
+    Toml,
+    /// A self-contained HTML page with a table of entries, for sharing
+    Html,
+    /// A JSON object per line for every entry, written as they're found, for
+    /// piping into e.g. `jq`
+    Jsonl,
+}
+
+/// Pager used when `$PAGER` isn't set, with `-R` so colors come through
+const DEFAULT_PAGER: &str = "less -R";
+
+/// When the listing is shown in a pager
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Paging {
+    /// Whenever stdout is a terminal
+    Always,
+    /// When stdout is a terminal and the listing doesn't fit on it
+    Auto,
+    Never,
+}
+
+/// Counts how many rows of a terminal the text written so far takes up,
+/// including lines wrapping around, to tell when it no longer fits. ANSI
+/// escape sequences like colors take up no space
+pub struct ScreenFit {
+    columns: usize,
+    /// How many rows there's room for, leaving one for the pager's prompt
+    rows: usize,
+    /// Rows filled up so far
+    filled: usize,
+    /// How far into the current row the text has come
+    column: usize,
+    escape: Escape,
+}
+
+/// How far into an escape sequence the text is
+#[derive(Clone, Copy)]
+enum Escape {
+    None,
+    /// Right after the escape character
+    Start,
+    /// Within a control sequence like `\x1b[1;32m`, until its final byte
+    Control,
+}
+
+impl ScreenFit {
+    /// Starts out on an empty terminal of the size of the one stdout is, if
+    /// it can be told
+    fn of_terminal() -> Option<Self> {
+        let (columns, rows) = ratatui::crossterm::terminal::size().ok()?;
+        Some(Self {
+            columns: usize::from(columns).max(1),
+            rows: usize::from(rows).saturating_sub(1),
+            filled: 0,
+            column: 0,
+            escape: Escape::None,
+        })
+    }
+
+    /// Takes `text` into account, returning whether everything so far still
+    /// fits
+    fn add(&mut self, text: &[u8]) -> bool {
+        for &byte in text {
+            self.escape = match (self.escape, byte) {
+                (Escape::None, 0x1b) => Escape::Start,
+                (Escape::Start, b'[') => Escape::Control,
+                (Escape::Start, _) => Escape::None,
+                (Escape::Control, 0x40..=0x7e) => Escape::None,
+                (Escape::Control, _) => Escape::Control,
+                (Escape::None, b'\n') => {
+                    self.filled += 1;
+                    self.column = 0;
+                    Escape::None
+                }
+                (Escape::None, b'\r') => {
+                    self.column = 0;
+                    Escape::None
+                }
+                (Escape::None, b'\t') => {
+                    self.column = (self.column / 8 + 1) * 8;
+                    Escape::None
+                }
+                // Continuation bytes of UTF-8 characters
+                (Escape::None, 0x80..=0xbf) => Escape::None,
+                (Escape::None, _) => {
+                    if self.column >= self.columns {
+                        self.filled += 1;
+                        self.column = 0;
+                    }
+                    self.column += 1;
+                    Escape::None
+                }
+            };
+        }
+
+        self.filled < self.rows || (self.filled == self.rows && self.column == 0)
+    }
+}
+
+/// Where the listing is written to
+pub enum Output {
+    /// Stdout, which is line buffered
+    Stdout(StdoutLock<'static>),
+    /// Stdout buffered in larger blocks, for when nobody is watching it line by line
+    BufferedStdout(BufWriter<StdoutLock<'static>>),
+    File(BufWriter<File>),
+    Pager {
+        stdin: BufWriter<ChildStdin>,
+        child: Child,
+    },
+    /// Stdout held back until it's clear whether the listing fits on the
+    /// terminal, moving to a pager once it doesn't
+    Pending {
+        buffer: HeldBack,
+        fit: ScreenFit,
+    },
+}
+
+/// Output held back from stdout, which is still written there if it's
+/// dropped before it's clear where it should go, like when the listing fails
+/// halfway
+#[derive(Default)]
+pub struct HeldBack(Vec<u8>);
+
+impl HeldBack {
+    /// Writes everything held back to stdout, leaving nothing to write on drop
+    fn write_to_stdout(&mut self) -> std::io::Result<()> {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&std::mem::take(&mut self.0))?;
+        stdout.flush()
+    }
+}
+
+impl Drop for HeldBack {
+    fn drop(&mut self) {
+        // There's no one left to report failing to write to
+        let _ = self.write_to_stdout();
+    }
+}
+
+impl Output {
+    /// Writes to `file` if given, to the user's pager if `paging` asks for it
+    /// and stdout is a terminal, and to stdout otherwise. Stdout is line
+    /// buffered if it's a terminal or `line_buffered` is requested. With
+    /// `assume_tty`, stdout is treated as a terminal even if it isn't, except
+    /// for telling whether the listing fits on it
+    pub fn new(
+        paging: Paging,
+        file: Option<&Path>,
+        line_buffered: bool,
+        assume_tty: bool,
+    ) -> anyhow::Result<Self> {
+        if let Some(file) = file {
+            let file = File::create(file).context(format!("Failed creating {}", file.display()))?;
+            return Ok(Self::File(BufWriter::new(file)));
+        }
+
+        let is_terminal = assume_tty || std::io::stdout().is_terminal();
+        if !is_terminal && !line_buffered {
+            return Ok(Self::BufferedStdout(BufWriter::new(
+                std::io::stdout().lock(),
+            )));
+        }
+        match paging {
+            Paging::Always if is_terminal => Self::pager(),
+            Paging::Auto if std::io::stdout().is_terminal() => match ScreenFit::of_terminal() {
+                Some(fit) => Ok(Self::Pending {
+                    buffer: HeldBack::default(),
+                    fit,
+                }),
+                None => Ok(Self::Stdout(std::io::stdout().lock())),
+            },
+            _ => Ok(Self::Stdout(std::io::stdout().lock())),
+        }
+    }
+
+    /// Starts the user's pager, writing to it from now on
+    fn pager() -> anyhow::Result<Self> {
+        let pager = std::env::var("PAGER")
+            .ok()
+            .filter(|pager| !pager.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_PAGER.into());
+        let mut args = pager.split_whitespace();
+        // SAFETY: We filtered out pagers that are only whitespace above
+        let program = args.next().unwrap();
+
+        let mut command = Command::new(program);
+        command.args(args).stdin(Stdio::piped());
+        if std::env::var_os("LESS").is_none() {
+            // Make a plain `PAGER=less` render our colors too
+            command.env("LESS", "-R");
+        }
+
+        let mut child = command
+            .spawn()
+            .context(format!("Failed starting pager {pager}"))?;
+        // SAFETY: We asked for stdin to be piped when spawning
+        let stdin = BufWriter::new(child.stdin.take().unwrap());
+
+        Ok(Self::Pager { stdin, child })
+    }
+
+    pub fn is_pager(&self) -> bool {
+        matches!(self, Self::Pager { .. })
+    }
+
+    /// Flushes everything written, and waits for the user to quit the pager
+    pub fn finish(self) -> anyhow::Result<()> {
+        match self {
+            Self::Stdout(mut stdout) => Ok(stdout.flush()?),
+            Self::BufferedStdout(mut stdout) => Ok(stdout.flush()?),
+            Self::File(mut file) => Ok(file.flush()?),
+            Self::Pager { stdin, mut child } => {
+                // Closing stdin tells the pager that there's no more to come
+                drop(stdin.into_inner().map_err(|error| error.into_error())?);
+                child.wait().context("Failed waiting for the pager")?;
+                Ok(())
+            }
+            // Everything fit, so there's no need for a pager
+            Self::Pending { mut buffer, .. } => Ok(buffer.write_to_stdout()?),
+        }
+    }
+}
+
+impl Write for Output {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Self::Stdout(stdout) => stdout.write(buf),
+            Self::BufferedStdout(stdout) => stdout.write(buf),
+            Self::File(file) => file.write(buf),
+            Self::Pager { stdin, .. } => stdin.write(buf),
+            Self::Pending { buffer, fit } => {
+                buffer.0.extend_from_slice(buf);
+                if !fit.add(buf) {
+                    let buffer = std::mem::take(&mut buffer.0);
+                    *self = Self::pager().unwrap_or_else(|error| {
+                        eprintln!(
+                            "{} {error:#}, writing to stdout instead",
+                            "warning:".yellow().bold()
+                        );
+                        Self::Stdout(std::io::stdout().lock())
+                    });
+                    self.write_all(&buffer)?;
+                }
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Self::Stdout(stdout) => stdout.flush(),
+            Self::BufferedStdout(stdout) => stdout.flush(),
+            Self::File(file) => file.flush(),
+            Self::Pager { stdin, .. } => stdin.flush(),
+            // Held back until it's clear whether a pager is needed
+            Self::Pending { .. } => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,189 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{mpsc, Mutex},
+};
+
+use ignore::{WalkBuilder, WalkState};
+
+use crate::{
+    explorer::Candidate,
+    follow::{WalkError, Walked},
+    stats,
+};
+
+/// How many items every thread maps at a time
+const BATCH_SIZE: usize = 256;
+
+/// Maps the items of an iterator on several threads, in batches, yielding the
+/// results in the same order as the items. With one thread, items are mapped
+/// one at a time as they're asked for, like `Iterator::map`
+pub struct ParallelMap<I: Iterator, F, R> {
+    items: I,
+    map: F,
+    threads: usize,
+    /// Results mapped ahead of time, in order
+    mapped: VecDeque<R>,
+}
+
+impl<I: Iterator, F: Fn(I::Item) -> R, R> ParallelMap<I, F, R> {
+    pub fn new(items: I, threads: usize, map: F) -> Self {
+        Self {
+            items,
+            map,
+            threads: threads.max(1),
+            mapped: VecDeque::new(),
+        }
+    }
+}
+
+impl<I, F, R> Iterator for ParallelMap<I, F, R>
+where
+    I: Iterator,
+    I::Item: Send,
+    F: Fn(I::Item) -> R + Sync,
+    R: Send,
+{
+    type Item = R;
+
+    fn next(&mut self) -> Option<R> {
+        if self.threads == 1 {
+            return self.items.next().map(&self.map);
+        }
+
+        if self.mapped.is_empty() {
+            let mut batch: Vec<_> = self
+                .items
+                .by_ref()
+                .take(self.threads * BATCH_SIZE)
+                .collect();
+            let chunk_size = batch.len().div_ceil(self.threads).max(1);
+            let mut chunks = Vec::new();
+            while !batch.is_empty() {
+                let rest = batch.split_off(chunk_size.min(batch.len()));
+                chunks.push(std::mem::replace(&mut batch, rest));
+            }
+
+            let map = &self.map;
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = chunks
+                    .into_iter()
+                    .map(|chunk| {
+                        scope.spawn(move || chunk.into_iter().map(map).collect::<Vec<_>>())
+                    })
+                    .collect();
+                for worker in workers {
+                    // Panics in a worker are passed on, as if it had happened here
+                    let results = worker
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+                    self.mapped.extend(results);
+                }
+            });
+        }
+
+        self.mapped.pop_front()
+    }
+}
+
+/// Walks everything below `root` on `threads` threads, each reading
+/// directories of its own, and returns what was found in the order it was
+/// found, which changes from run to run. Like `WalkDir::filter_entry`, `keep`
+/// sees the entries at least `min_depth` deep and leaves out those it rejects
+/// along with what's within them. With `follow`, symlinks are followed, but
+/// like [`crate::follow::FollowDepth`] only directories reached through at
+/// most that many are descended into
+pub fn walk_tree(
+    root: &Path,
+    threads: usize,
+    min_depth: usize,
+    max_depth: usize,
+    follow: Option<usize>,
+    keep: impl Fn(&Candidate) -> bool + Sync,
+) -> Vec<Result<Walked, WalkError>> {
+    // A root that's a symlink to a directory is descended into either way,
+    // like `WalkDir` does, which only happens through the link with a trailing
+    // separator
+    let walked_root = if root.is_symlink() && root.is_dir() {
+        root.join("")
+    } else {
+        root.to_path_buf()
+    };
+    let walk = WalkBuilder::new(&walked_root)
+        .standard_filters(false)
+        .follow_links(follow.is_some())
+        .max_depth(Some(max_depth))
+        .threads(threads)
+        .build_parallel();
+
+    let (sender, found) = mpsc::channel();
+    // How many symlinks were followed to reach directories reached through
+    // any at all
+    let links: Mutex<HashMap<PathBuf, usize>> = Mutex::new(HashMap::new());
+    walk.run(|| {
+        let sender = sender.clone();
+        let (keep, links) = (&keep, &links);
+        Box::new(move |entry| {
+            // SAFETY: The receiver is only dropped once the walk is done
+            let send = |walked| sender.send(walked).unwrap();
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    let error = WalkError::from(error);
+                    send(Walked::broken_link(&error).ok_or(error));
+                    return WalkState::Continue;
+                }
+            };
+            let depth = entry.depth();
+            if depth < min_depth {
+                return WalkState::Continue;
+            }
+            let walked = if depth == 0 {
+                // The root as given, rather than with a trailing separator
+                match Walked::at(root, follow.is_some()) {
+                    Ok(walked) => walked,
+                    Err(error) => {
+                        send(Err(error));
+                        return WalkState::Skip;
+                    }
+                }
+            } else {
+                Walked::Parallel(entry)
+            };
+
+            stats::record_visit();
+            let is_dir = walked.file_type().is_dir();
+            let metadata = || match &walked {
+                Walked::Entry(entry) => entry.metadata().ok(),
+                Walked::Parallel(entry) => entry.metadata().ok(),
+                Walked::BrokenLink { path, .. } => path.symlink_metadata().ok(),
+            };
+            if !keep(&Candidate::new(walked.path(), depth, is_dir, &metadata)) {
+                return WalkState::Skip;
+            }
+
+            let mut state = WalkState::Continue;
+            if is_dir {
+                // SAFETY: Nothing panics while holding the lock
+                let mut links = links.lock().unwrap();
+                let parent_links = walked
+                    .path()
+                    .parent()
+                    .and_then(|parent| links.get(parent))
+                    .copied()
+                    .unwrap_or(0);
+                let walked_links = parent_links + usize::from(walked.path_is_symlink());
+                if walked_links > follow.unwrap_or(0) {
+                    // Listed, but not descended into
+                    state = WalkState::Skip;
+                } else if walked_links > 0 {
+                    links.insert(walked.path().to_path_buf(), walked_links);
+                }
+            }
+            send(Ok(walked));
+            state
+        })
+    });
+    drop(sender);
+    found.into_iter().collect()
+}
@@ -0,0 +1,58 @@
+use std::{io::Write, sync::Arc, time::UNIX_EPOCH};
+
+use arrow_array::{
+    builder::{BooleanBuilder, StringBuilder, TimestampMillisecondBuilder, UInt64Builder},
+    Array, ArrayRef, RecordBatch,
+};
+use arrow_schema::{DataType, Field, Schema};
+use parquet::arrow::ArrowWriter;
+
+use crate::entry::Entry;
+
+/// The listing gathered column by column, to be written as one Parquet file
+#[derive(Default)]
+pub struct ParquetBuilder {
+    paths: StringBuilder,
+    sizes: UInt64Builder,
+    modified: TimestampMillisecondBuilder,
+    is_dir: BooleanBuilder,
+}
+
+impl ParquetBuilder {
+    pub fn add(&mut self, entry: &Entry) {
+        self.paths.append_value(entry.path.to_string_lossy());
+        self.sizes.append_value(entry.size);
+        self.modified.append_option(
+            entry
+                .metadata
+                .modified()
+                .ok()
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .and_then(|since_epoch| i64::try_from(since_epoch.as_millis()).ok()),
+        );
+        self.is_dir.append_value(entry.metadata.is_dir());
+    }
+
+    /// Writes all entries added as a single row group to `writer`
+    pub fn finish(mut self, writer: impl Write + Send) -> anyhow::Result<()> {
+        let modified = self.modified.finish().with_timezone("UTC");
+        let schema = Arc::new(Schema::new(vec![
+            Field::new("path", DataType::Utf8, false),
+            Field::new("size", DataType::UInt64, false),
+            Field::new("mtime", modified.data_type().clone(), true),
+            Field::new("is_dir", DataType::Boolean, false),
+        ]));
+        let columns: Vec<ArrayRef> = vec![
+            Arc::new(self.paths.finish()),
+            Arc::new(self.sizes.finish()),
+            Arc::new(modified),
+            Arc::new(self.is_dir.finish()),
+        ];
+
+        let batch = RecordBatch::try_new(schema.clone(), columns)?;
+        let mut writer = ArrowWriter::try_new(writer, schema, None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Resolves `path` to a canonical absolute path, falling back to making it
+/// absolute without resolving anything if it can't be canonicalized. Any
+/// symlinks are resolved, so this is the path of what `path` points to
+pub fn resolved_path(path: &Path) -> PathBuf {
+    path.canonicalize()
+        .or_else(|_| std::path::absolute(path))
+        .unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// The absolute path of the entry at `path` itself, with the directories
+/// leading to it resolved but not its last component, so a symlink stays the
+/// symlink rather than becoming what it points to. Falls back to making it
+/// absolute without resolving anything if its parent can't be canonicalized
+pub fn absolute_path(path: &Path) -> PathBuf {
+    let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+        // Paths like `.`, `..` or `/` don't end in a name that could be a symlink
+        return resolved_path(path);
+    };
+    let parent = if parent.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        parent
+    };
+    match parent.canonicalize() {
+        Ok(parent) => parent.join(name),
+        Err(_) => std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf()),
+    }
+}
+
+/// The absolute path of an entry at `path` within the walk `root`, which
+/// itself resolves to `absolute_root`
+pub fn absolute_entry_path(path: &Path, root: &Path, absolute_root: &Path) -> PathBuf {
+    match path.strip_prefix(root) {
+        // Collecting the components drops the trailing slash joining "" adds
+        Ok(relative) => absolute_root.join(relative).components().collect(),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// `path` relative to `base`, or `path` itself if it isn't within `base`
+pub fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    match path.strip_prefix(base) {
+        Ok(relative) if relative.as_os_str().is_empty() => ".".into(),
+        Ok(relative) => relative.to_path_buf(),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// `path` with `.` components dropped and `..` components removing the one
+/// before them, without looking at the filesystem. Unlike canonicalizing, a
+/// `..` after a symlink leaves the symlink rather than going to its target's
+/// parent
+pub fn resolve_dots(path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match resolved.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    resolved.pop();
+                }
+                // There's nothing above the root
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                Some(Component::ParentDir | Component::CurDir) | None => {
+                    resolved.push(Component::ParentDir)
+                }
+            },
+            component => resolved.push(component),
+        }
+    }
+
+    if resolved.as_os_str().is_empty() {
+        ".".into()
+    } else {
+        resolved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_to_the_base_itself_is_dot() {
+        assert_eq!(
+            relative_to(Path::new("/home/user"), Path::new("/home/user")),
+            Path::new(".")
+        );
+    }
+
+    #[test]
+    fn relative_to_a_base_containing_the_path_strips_it() {
+        assert_eq!(
+            relative_to(Path::new("/home/user/src/main.rs"), Path::new("/home/user")),
+            Path::new("src/main.rs")
+        );
+    }
+
+    #[test]
+    fn relative_to_a_base_not_containing_the_path_keeps_it() {
+        assert_eq!(
+            relative_to(Path::new("/etc/hosts"), Path::new("/home/user")),
+            Path::new("/etc/hosts")
+        );
+    }
+
+    #[test]
+    fn resolve_dots_drops_dots_and_what_parents_undo() {
+        assert_eq!(resolve_dots(Path::new("./a/../b")), Path::new("b"));
+    }
+
+    #[test]
+    fn resolve_dots_keeps_leading_parents() {
+        assert_eq!(resolve_dots(Path::new("../a")), Path::new("../a"));
+        assert_eq!(resolve_dots(Path::new("../../a/..")), Path::new("../.."));
+    }
+
+    #[test]
+    fn resolve_dots_stays_at_the_root() {
+        assert_eq!(resolve_dots(Path::new("/..")), Path::new("/"));
+        assert_eq!(resolve_dots(Path::new("/../a")), Path::new("/a"));
+    }
+
+    #[test]
+    fn resolve_dots_of_only_dots_is_dot() {
+        assert_eq!(resolve_dots(Path::new(".")), Path::new("."));
+        assert_eq!(resolve_dots(Path::new("a/..")), Path::new("."));
+    }
+}
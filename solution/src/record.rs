@@ -0,0 +1,96 @@
+use std::{fs::FileType, io::Write};
+
+use schemars::JsonSchema;
+use serde::Serialize;
+
+use crate::entry::Entry;
+
+/// How an entry is represented in the machine-readable `--format`s
+#[derive(Serialize, JsonSchema)]
+pub struct Record {
+    pub path: String,
+    pub name: String,
+    /// One of `file`, `dir`, `symlink` or `other`
+    pub kind: &'static str,
+    pub size: u64,
+    /// RFC 3339 timestamp in UTC, if the platform supports it
+    pub modified: Option<String>,
+    pub depth: usize,
+    /// What a symlink points to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+}
+
+impl From<&Entry> for Record {
+    fn from(entry: &Entry) -> Self {
+        Self {
+            path: entry.path.to_string_lossy().into_owned(),
+            name: entry.name.clone(),
+            kind: kind(entry.metadata.file_type()),
+            size: entry.size,
+            modified: entry
+                .metadata
+                .modified()
+                .ok()
+                .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339()),
+            depth: entry.depth,
+            target: entry
+                .link_target
+                .as_ref()
+                .map(|target| target.to_string_lossy().into_owned()),
+        }
+    }
+}
+
+/// The name of the kind of `file_type`: `file`, `dir`, `symlink` or `other`
+pub fn kind(file_type: FileType) -> &'static str {
+    if file_type.is_file() {
+        "file"
+    } else if file_type.is_dir() {
+        "dir"
+    } else if file_type.is_symlink() {
+        "symlink"
+    } else {
+        "other"
+    }
+}
+
+/// The columns of `--format tsv` and `--format csv`, written as their header
+pub const COLUMNS: [&str; 4] = ["path", "size_bytes", "modified_rfc3339", "kind"];
+
+impl Record {
+    /// Writes the path, size, modification time and kind separated by tabs.
+    /// Tabs, newlines and backslashes in the path are escaped so every entry
+    /// stays on one line with exactly four fields
+    pub fn write_tsv(&self, mut output: impl Write) -> std::io::Result<()> {
+        let path = escape_tsv(&self.path);
+        let modified = self.modified.as_deref().unwrap_or_default();
+        writeln!(output, "{path}\t{}\t{modified}\t{}", self.size, self.kind)
+    }
+
+    /// Writes the path, size, modification time and kind separated by commas,
+    /// quoting the path if needed like RFC 4180 describes
+    pub fn write_csv(&self, mut output: impl Write) -> std::io::Result<()> {
+        let path = escape_csv(&self.path);
+        let modified = self.modified.as_deref().unwrap_or_default();
+        writeln!(output, "{path},{},{modified},{}", self.size, self.kind)
+    }
+}
+
+fn escape_tsv(field: &str) -> String {
+    field
+        .replace('\\', r"\\")
+        .replace('\t', r"\t")
+        .replace('\n', r"\n")
+        .replace('\r', r"\r")
+}
+
+/// Quotes `field` if it contains a comma, quote or line break, doubling any
+/// quotes within it
+fn escape_csv(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
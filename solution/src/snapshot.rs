@@ -0,0 +1,94 @@
+use std::{
+    collections::BTreeMap,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+    time::SystemTime,
+};
+
+use anyhow::Context;
+use bytesize::ByteSize;
+use serde::{Deserialize, Serialize};
+
+use crate::{entry::Entry, theme::Theme};
+
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+struct SnapshotEntry {
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+/// The size and modification time of every entry listed, keyed by path
+/// relative to the root, saved by `--snapshot` and compared by `--diff`
+#[derive(Serialize, Deserialize, Default)]
+pub struct Snapshot(BTreeMap<String, SnapshotEntry>);
+
+impl Snapshot {
+    pub fn load(file: &Path) -> anyhow::Result<Self> {
+        let reader = BufReader::new(
+            File::open(file).context(format!("Failed opening snapshot {}", file.display()))?,
+        );
+        serde_json::from_reader(reader)
+            .context(format!("Failed reading snapshot {}", file.display()))
+    }
+
+    pub fn save(&self, file: &Path) -> anyhow::Result<()> {
+        let mut writer = BufWriter::new(
+            File::create(file).context(format!("Failed creating snapshot {}", file.display()))?,
+        );
+        serde_json::to_writer(&mut writer, self)
+            .context(format!("Failed writing snapshot {}", file.display()))?;
+        Ok(writer.flush()?)
+    }
+
+    /// Adds `entry`, found at `path` relative to the root
+    pub fn add(&mut self, path: &Path, entry: &Entry) {
+        self.0.insert(
+            path.to_string_lossy().into_owned(),
+            SnapshotEntry {
+                size: entry.size,
+                modified: entry.metadata.modified().ok(),
+            },
+        );
+    }
+
+    /// Writes a line for every entry added (`+`), removed (`-`) or modified
+    /// (`~`, when its size or modification time changed) since `previous`,
+    /// ordered by path, with markers styled by `theme`
+    pub fn diff(
+        &self,
+        previous: &Self,
+        mut output: impl Write,
+        theme: &Theme,
+    ) -> std::io::Result<()> {
+        let mut changes: Vec<_> = self
+            .0
+            .iter()
+            .filter_map(|(path, entry)| match previous.0.get(path) {
+                None => Some((path, theme.added.paint("+"), "".to_owned())),
+                Some(previous) if previous.size != entry.size => Some((
+                    path,
+                    theme.changed.paint("~"),
+                    format!(" ({} → {})", ByteSize(previous.size), ByteSize(entry.size)),
+                )),
+                Some(previous) if previous != entry => {
+                    Some((path, theme.changed.paint("~"), "".to_owned()))
+                }
+                Some(_) => None,
+            })
+            .chain(
+                previous
+                    .0
+                    .keys()
+                    .filter(|path| !self.0.contains_key(*path))
+                    .map(|path| (path, theme.removed.paint("-"), "".to_owned())),
+            )
+            .collect();
+        changes.sort_by_key(|(path, ..)| *path);
+
+        for (path, marker, details) in changes {
+            writeln!(output, "{marker} {path}{details}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,103 @@
+use clap::ValueEnum;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+
+use crate::entry::Entry;
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SortKey {
+    /// Shuffle the entries, e.g. to take a random sample with `--limit`
+    Random,
+    /// Directories with the most immediate children first
+    Children,
+    /// Largest entries first
+    Size,
+    /// Most recently modified first, or for `--newest-in-dir` the directories
+    /// with the most recently modified files
+    Modified,
+    /// Most recently changed inode first, which includes permission and owner
+    /// changes. This is Unix's ctime, not the creation time
+    #[cfg(unix)]
+    Ctime,
+    /// Alphabetically by name
+    Name,
+    /// Directories first, then files, symlinks and anything else, each by name
+    Type,
+}
+
+/// Sorts the whole listing by `key`. `seed` makes random shuffles reproducible.
+/// Directories rank by size only if `directory_totals` gave them the size of
+/// their contents, and as empty otherwise, as their own size isn't listed
+pub fn sort(entries: &mut [Entry], key: SortKey, seed: Option<u64>, directory_totals: bool) {
+    match key {
+        SortKey::Random => {
+            let mut rng = seed.map_or_else(rand::make_rng, StdRng::seed_from_u64);
+            entries.shuffle(&mut rng);
+        }
+        SortKey::Children => entries.sort_by_key(|entry| std::cmp::Reverse(entry.children)),
+        SortKey::Size => entries.sort_by_key(|entry| {
+            let is_own_size = entry.metadata.is_dir() && !directory_totals;
+            std::cmp::Reverse(if is_own_size { 0 } else { entry.size })
+        }),
+        SortKey::Modified => entries.sort_by_key(|entry| {
+            std::cmp::Reverse(
+                entry
+                    .newest_file
+                    .as_ref()
+                    .map(|(_, modified)| *modified)
+                    .or_else(|| entry.metadata.modified().ok()),
+            )
+        }),
+        #[cfg(unix)]
+        SortKey::Ctime => entries.sort_by_key(|entry| std::cmp::Reverse(entry.changed())),
+        SortKey::Name => entries.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortKey::Type => entries.sort_by(|a, b| {
+            type_order(a)
+                .cmp(&type_order(b))
+                .then_with(|| a.name.cmp(&b.name))
+        }),
+    }
+}
+
+fn type_order(entry: &Entry) -> u8 {
+    let file_type = entry.metadata.file_type();
+    if file_type.is_dir() {
+        0
+    } else if file_type.is_file() {
+        1
+    } else if file_type.is_symlink() {
+        2
+    } else {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// The names of `entries` in order
+    fn names(entries: &[Entry]) -> Vec<&str> {
+        entries.iter().map(|entry| entry.name.as_str()).collect()
+    }
+
+    #[test]
+    fn size_ranks_directories_as_empty_without_totals() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir(root.path().join("directory")).unwrap();
+        fs::write(root.path().join("small"), "a").unwrap();
+        fs::write(root.path().join("large"), "abc").unwrap();
+        let mut entries: Vec<_> = ["directory", "small", "large"]
+            .into_iter()
+            .map(|name| Entry::at(&root.path().join(name)).unwrap())
+            .collect();
+
+        sort(&mut entries, SortKey::Size, None, false);
+        assert_eq!(names(&entries), ["large", "small", "directory"]);
+
+        entries[2].size = 10;
+        sort(&mut entries, SortKey::Size, None, true);
+        assert_eq!(names(&entries), ["directory", "large", "small"]);
+    }
+}
@@ -0,0 +1,51 @@
+//! Lightweight instrumentation of the walk, reported with `--stats`
+
+use std::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+static ENTRIES_VISITED: AtomicUsize = AtomicUsize::new(0);
+static STAT_CALLS: AtomicUsize = AtomicUsize::new(0);
+static METADATA_NANOS: AtomicU64 = AtomicU64::new(0);
+
+/// How much work the walks in this process have done so far
+#[derive(Clone, Copy, Debug)]
+pub struct Stats {
+    /// Entries the walks found, before any filtering
+    pub entries_visited: usize,
+    /// Times the filesystem was hit for metadata
+    pub stat_calls: usize,
+    /// The time spent reading metadata, summed up over every thread
+    pub metadata_time: Duration,
+}
+
+impl Stats {
+    pub fn collected() -> Self {
+        Self {
+            entries_visited: ENTRIES_VISITED.load(Ordering::Relaxed),
+            stat_calls: STAT_CALLS.load(Ordering::Relaxed),
+            metadata_time: Duration::from_nanos(METADATA_NANOS.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Records that the walk yielded an entry, before any filtering
+pub(crate) fn record_visit() {
+    ENTRIES_VISITED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Runs `stat`, a function hitting the filesystem for metadata, counting it
+pub(crate) fn stat_call<T>(stat: impl FnOnce() -> T) -> T {
+    STAT_CALLS.fetch_add(1, Ordering::Relaxed);
+    stat()
+}
+
+/// Runs `f`, adding the time it took to the time spent reading metadata
+pub(crate) fn time_metadata<T>(f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    let elapsed = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+    METADATA_NANOS.fetch_add(elapsed, Ordering::Relaxed);
+    result
+}
@@ -0,0 +1,112 @@
+use std::{collections::HashSet, fmt::Display};
+
+use bytesize::ByteSize;
+
+use crate::{du, entry::Entry, theme::Theme};
+
+/// How many of each kind of entry were listed and how large the regular files
+/// are in total, printed after the listing by `--summary`
+#[derive(Default)]
+pub struct Summary {
+    files: usize,
+    directories: usize,
+    symlinks: usize,
+    /// Sockets, devices and the like
+    others: usize,
+    /// The total size of the regular files, as directories would count their
+    /// contents a second time with `--du`
+    bytes: u64,
+    /// Whether files with several hard links only count towards `bytes` once
+    deduplicate_hardlinks: bool,
+    /// The hard linked files counted so far, with `deduplicate_hardlinks`
+    seen_files: HashSet<(u64, u64)>,
+}
+
+impl Summary {
+    /// A summary of nothing yet. With `deduplicate_hardlinks`, files with
+    /// several hard links are counted as files every time but their size only
+    /// once, like `du` does
+    pub fn new(deduplicate_hardlinks: bool) -> Self {
+        Self {
+            deduplicate_hardlinks,
+            ..Self::default()
+        }
+    }
+
+    pub fn add(&mut self, entry: &Entry) {
+        let file_type = entry.metadata.file_type();
+        if file_type.is_dir() {
+            self.directories += 1;
+            return;
+        }
+
+        if file_type.is_symlink() {
+            self.symlinks += 1;
+            return;
+        }
+        if !file_type.is_file() {
+            self.others += 1;
+            return;
+        }
+
+        self.files += 1;
+        if !self.deduplicate_hardlinks || du::is_first_link(&entry.metadata, &mut self.seen_files) {
+            self.bytes += entry.size;
+        }
+    }
+}
+
+/// The summary as a line like `12 files, 3 directories, 1 symlink, 4.2 MB`
+pub struct FormatSummary<'a> {
+    pub summary: &'a Summary,
+    pub theme: &'a Theme,
+}
+
+impl Display for FormatSummary<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let count = |count: usize, singular: &str, plural: &str| {
+            format!("{count} {}", if count == 1 { singular } else { plural })
+        };
+        let summary = self.summary;
+        let mut parts = vec![
+            count(summary.files, "file", "files"),
+            count(summary.directories, "directory", "directories"),
+            count(summary.symlinks, "symlink", "symlinks"),
+        ];
+        if summary.others > 0 {
+            parts.push(count(summary.others, "other", "others"));
+        }
+
+        write!(
+            f,
+            "{}, {}",
+            self.theme.header.paint(&parts.join(", ")),
+            self.theme.size.paint(&ByteSize(summary.bytes).to_string())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn only_regular_files_count_towards_the_size() {
+        let root = tempfile::tempdir().unwrap();
+        fs::write(root.path().join("file"), "abc").unwrap();
+        std::os::unix::fs::symlink("file", root.path().join("link")).unwrap();
+
+        let mut summary = Summary::new(false);
+        summary.add(&Entry::at(root.path()).unwrap());
+        summary.add(&Entry::at(&root.path().join("file")).unwrap());
+        summary.add(&Entry::link_at(&root.path().join("link")).unwrap());
+        assert_eq!(
+            (summary.files, summary.directories, summary.symlinks),
+            (1, 1, 1)
+        );
+        assert_eq!(summary.bytes, 3);
+    }
+}
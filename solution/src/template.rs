@@ -0,0 +1,100 @@
+use bytesize::ByteSize;
+
+use crate::entry::Entry;
+
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Path,
+    Name,
+    /// Human-readable size
+    Size,
+    /// Size in bytes
+    Bytes,
+    Modified,
+    Depth,
+}
+
+#[derive(Debug)]
+enum Part {
+    Literal(String),
+    Field(Field),
+}
+
+/// A line printed for every entry by `--path-pattern`, like `find -printf`
+#[derive(Debug)]
+pub struct Template(Vec<Part>);
+
+impl Template {
+    /// Parses `template`, where `{path}`, `{name}`, `{size}`, `{bytes}`,
+    /// `{modified}` and `{depth}` are placeholders. `{{` and `}}` are literal
+    /// braces
+    pub fn parse(template: &str) -> anyhow::Result<Self> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.chars();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    literal.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    literal.push('}');
+                }
+                '{' => {
+                    let Some((name, rest)) = chars.as_str().split_once('}') else {
+                        anyhow::bail!("Unclosed {{ in {template}");
+                    };
+                    let field = match name {
+                        "path" => Field::Path,
+                        "name" => Field::Name,
+                        "size" => Field::Size,
+                        "bytes" => Field::Bytes,
+                        "modified" => Field::Modified,
+                        "depth" => Field::Depth,
+                        _ => anyhow::bail!(
+                            "Unknown placeholder {{{name}}}, expected one of {{path}}, {{name}}, {{size}}, {{bytes}}, {{modified}} or {{depth}}"
+                        ),
+                    };
+                    chars = rest.chars();
+                    if !literal.is_empty() {
+                        parts.push(Part::Literal(std::mem::take(&mut literal)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                '}' => anyhow::bail!("Unmatched }} in {template}, use }}}} for a literal one"),
+                c => literal.push(c),
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Ok(Self(parts))
+    }
+
+    /// The line for `entry`, with the placeholders filled in
+    pub fn render(&self, entry: &Entry) -> String {
+        let mut line = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Literal(literal) => line.push_str(literal),
+                Part::Field(Field::Path) => line.push_str(&entry.path.to_string_lossy()),
+                Part::Field(Field::Name) => line.push_str(&entry.name),
+                Part::Field(Field::Size) => line.push_str(&ByteSize(entry.size).to_string()),
+                Part::Field(Field::Bytes) => line.push_str(&entry.size.to_string()),
+                Part::Field(Field::Modified) => {
+                    if let Ok(modified) = entry.metadata.modified() {
+                        line.push_str(
+                            &chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339(),
+                        );
+                    }
+                }
+                Part::Field(Field::Depth) => line.push_str(&entry.depth.to_string()),
+            }
+        }
+        line
+    }
+}
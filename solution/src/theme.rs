@@ -0,0 +1,388 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::Context;
+use clap::ValueEnum;
+use colored::{Color, ColoredString, Colorize};
+use serde::Deserialize;
+
+use crate::filter;
+
+/// When to color the listing, chosen with `--color`
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ColorWhen {
+    /// When stdout is a terminal and `NO_COLOR` isn't set
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+impl ColorWhen {
+    /// Turns colors on or off for everything printed from now on. With
+    /// `assume_tty`, `auto` colors even when stdout isn't a terminal, but never
+    /// when writing `to_file`
+    pub fn apply(self, assume_tty: bool, to_file: bool) {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+        match self {
+            Self::Always => colored::control::set_override(true),
+            Self::Never => colored::control::set_override(false),
+            Self::Auto if no_color || to_file => colored::control::set_override(false),
+            Self::Auto if assume_tty => colored::control::set_override(true),
+            Self::Auto => {}
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ThemeName {
+    /// Bright colors for dark terminals
+    #[default]
+    Dark,
+    /// Darker colors readable on light terminals
+    Light,
+    /// No colors, only bold, italic and dimmed text
+    Mono,
+    /// Blue and yellow contrasts with bold and underlines instead of red and
+    /// green, for deuteranopia and protanopia (red-green color blindness).
+    /// Only uses the basic 16 terminal colors
+    Colorblind,
+}
+
+/// How a piece of text is styled
+#[derive(Clone, Copy, Default)]
+pub struct Style {
+    pub color: Option<Color>,
+    pub bold: bool,
+    pub dimmed: bool,
+    pub italic: bool,
+    pub underline: bool,
+}
+
+impl Style {
+    const fn plain() -> Self {
+        Self {
+            color: None,
+            bold: false,
+            dimmed: false,
+            italic: false,
+            underline: false,
+        }
+    }
+
+    const fn color(color: Color) -> Self {
+        Self {
+            color: Some(color),
+            ..Self::plain()
+        }
+    }
+
+    const fn bold(self) -> Self {
+        Self { bold: true, ..self }
+    }
+
+    const fn dimmed(self) -> Self {
+        Self {
+            dimmed: true,
+            ..self
+        }
+    }
+
+    const fn italic(self) -> Self {
+        Self {
+            italic: true,
+            ..self
+        }
+    }
+
+    const fn underline(self) -> Self {
+        Self {
+            underline: true,
+            ..self
+        }
+    }
+
+    pub fn paint(&self, text: &str) -> ColoredString {
+        let mut text = text.normal();
+        if let Some(color) = self.color {
+            text = text.color(color);
+        }
+        if self.bold {
+            text = text.bold();
+        }
+        if self.dimmed {
+            text = text.dimmed();
+        }
+        if self.italic {
+            text = text.italic();
+        }
+        if self.underline {
+            text = text.underline();
+        }
+        text
+    }
+}
+
+/// The styles of everything in the listing, chosen with `--theme`
+pub struct Theme {
+    pub file: Style,
+    pub dir: Style,
+    /// Symlinks and anything else that isn't a file or directory
+    pub symlink: Style,
+    pub size: Style,
+    pub modified: Style,
+    /// Indentation, dashes and other things in the background
+    pub background: Style,
+    pub header: Style,
+    /// Tags after names, like `[mount]`
+    pub tag: Style,
+    /// Things that look wrong, like files modified before they were created
+    pub anomaly: Style,
+    /// The `+`, `~` and `-` of entries added, changed or removed since a
+    /// `--diff` snapshot or while `--watch`ing
+    pub added: Style,
+    pub changed: Style,
+    pub removed: Style,
+    /// What `--checksum-verify` prints for files that matched their hash, didn't or
+    /// weren't found
+    pub ok: Style,
+    pub failed: Style,
+    pub missing: Style,
+    /// The `errors:` prefix of `--summarize-errors`
+    pub error: Style,
+    /// `--histogram` bars, from the smallest to the largest
+    pub bars: [Style; 3],
+    /// Styles of files by their lowercase extension, used instead of `file`
+    pub extensions: HashMap<String, Style>,
+}
+
+impl Theme {
+    pub fn of(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Self {
+                file: Style::color(Color::White),
+                dir: Style::color(Color::Blue),
+                symlink: Style::color(Color::Yellow),
+                size: Style::color(Color::Green),
+                modified: Style::color(Color::Blue),
+                background: Style::plain().dimmed(),
+                header: Style::plain().bold().underline(),
+                tag: Style::color(Color::Cyan).bold(),
+                anomaly: Style::color(Color::Red).bold(),
+                added: Style::color(Color::Green).bold(),
+                changed: Style::color(Color::Yellow).bold(),
+                removed: Style::color(Color::Red).bold(),
+                ok: Style::color(Color::Green),
+                failed: Style::color(Color::Red),
+                missing: Style::color(Color::Yellow),
+                error: Style::color(Color::Red).bold(),
+                bars: [
+                    Style::color(Color::Green),
+                    Style::color(Color::Yellow),
+                    Style::color(Color::Red),
+                ],
+                extensions: HashMap::new(),
+            },
+            ThemeName::Light => Self {
+                file: Style::color(Color::Black),
+                dir: Style::color(Color::Blue).bold(),
+                symlink: Style::color(Color::Magenta),
+                size: Style::color(Color::Green),
+                modified: Style::color(Color::Blue),
+                background: Style::color(Color::BrightBlack),
+                header: Style::plain().bold().underline(),
+                tag: Style::color(Color::Cyan).bold(),
+                anomaly: Style::color(Color::Red).bold(),
+                added: Style::color(Color::Green).bold(),
+                changed: Style::color(Color::Magenta).bold(),
+                removed: Style::color(Color::Red).bold(),
+                ok: Style::color(Color::Green),
+                failed: Style::color(Color::Red),
+                missing: Style::color(Color::Magenta),
+                error: Style::color(Color::Red).bold(),
+                bars: [
+                    Style::color(Color::Green),
+                    Style::color(Color::Magenta),
+                    Style::color(Color::Red),
+                ],
+                extensions: HashMap::new(),
+            },
+            ThemeName::Mono => Self {
+                file: Style::plain(),
+                dir: Style::plain().bold(),
+                symlink: Style::plain().italic(),
+                size: Style::plain(),
+                modified: Style::plain(),
+                background: Style::plain().dimmed(),
+                header: Style::plain().bold().underline(),
+                tag: Style::plain().bold(),
+                anomaly: Style::plain().bold().underline(),
+                added: Style::plain().bold(),
+                changed: Style::plain().italic(),
+                removed: Style::plain().dimmed(),
+                ok: Style::plain(),
+                failed: Style::plain().bold().underline(),
+                missing: Style::plain().italic(),
+                error: Style::plain().bold(),
+                bars: [
+                    Style::plain().dimmed(),
+                    Style::plain(),
+                    Style::plain().bold(),
+                ],
+                extensions: HashMap::new(),
+            },
+            ThemeName::Colorblind => Self {
+                file: Style::plain(),
+                dir: Style::color(Color::Blue).bold(),
+                symlink: Style::color(Color::Yellow).underline(),
+                size: Style::color(Color::Cyan),
+                modified: Style::color(Color::Blue),
+                background: Style::plain().dimmed(),
+                header: Style::plain().bold().underline(),
+                tag: Style::color(Color::Blue).underline(),
+                anomaly: Style::color(Color::Yellow).bold().underline(),
+                added: Style::color(Color::Blue).bold(),
+                changed: Style::color(Color::Cyan).bold(),
+                removed: Style::color(Color::Yellow).bold().underline(),
+                ok: Style::color(Color::Blue),
+                failed: Style::color(Color::Yellow).underline(),
+                missing: Style::color(Color::Cyan),
+                error: Style::color(Color::Yellow).bold(),
+                bars: [
+                    Style::color(Color::Blue),
+                    Style::color(Color::Cyan),
+                    Style::color(Color::Yellow).bold(),
+                ],
+                extensions: HashMap::new(),
+            },
+        }
+    }
+
+    /// The style of a file named `name`, by its extension if the theme has a
+    /// style for it
+    pub fn file_style(&self, name: &str) -> Style {
+        filter::extension(name, true)
+            .and_then(|extension| self.extensions.get(&extension))
+            .copied()
+            .unwrap_or(self.file)
+    }
+
+    /// Replaces the styles given in the TOML `file`, like
+    ///
+    /// ```toml
+    /// dir = { color = "blue", bold = true }
+    /// symlink = "yellow"
+    ///
+    /// [extensions]
+    /// rs = "red"
+    /// ```
+    ///
+    /// with `file`, `dir`, `symlink`, `size`, `modified`, `background`,
+    /// `header`, `tag`, `anomaly`, `added`, `changed`, `removed`, `ok`,
+    /// `failed`, `missing` and `error` styles, and per extension styles for
+    /// files
+    pub fn load_file(&mut self, file: &Path) -> anyhow::Result<()> {
+        let contents = std::fs::read_to_string(file)
+            .context(format!("Failed reading theme {}", file.display()))?;
+        let theme: ThemeFile = toml::from_str(&contents)
+            .context(format!("Failed parsing theme {}", file.display()))?;
+
+        for (style, spec) in [
+            (&mut self.file, theme.file),
+            (&mut self.dir, theme.dir),
+            (&mut self.symlink, theme.symlink),
+            (&mut self.size, theme.size),
+            (&mut self.modified, theme.modified),
+            (&mut self.background, theme.background),
+            (&mut self.header, theme.header),
+            (&mut self.tag, theme.tag),
+            (&mut self.anomaly, theme.anomaly),
+            (&mut self.added, theme.added),
+            (&mut self.changed, theme.changed),
+            (&mut self.removed, theme.removed),
+            (&mut self.ok, theme.ok),
+            (&mut self.failed, theme.failed),
+            (&mut self.missing, theme.missing),
+            (&mut self.error, theme.error),
+        ] {
+            if let Some(spec) = spec {
+                *style = spec
+                    .style()
+                    .context(format!("In theme {}", file.display()))?;
+            }
+        }
+        for (extension, spec) in theme.extensions {
+            let style = spec
+                .style()
+                .context(format!("In theme {}", file.display()))?;
+            self.extensions.insert(extension.to_lowercase(), style);
+        }
+        Ok(())
+    }
+}
+
+/// The styles a `--theme-file` overrides
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ThemeFile {
+    file: Option<StyleSpec>,
+    dir: Option<StyleSpec>,
+    symlink: Option<StyleSpec>,
+    size: Option<StyleSpec>,
+    modified: Option<StyleSpec>,
+    background: Option<StyleSpec>,
+    header: Option<StyleSpec>,
+    tag: Option<StyleSpec>,
+    anomaly: Option<StyleSpec>,
+    added: Option<StyleSpec>,
+    changed: Option<StyleSpec>,
+    removed: Option<StyleSpec>,
+    ok: Option<StyleSpec>,
+    failed: Option<StyleSpec>,
+    missing: Option<StyleSpec>,
+    error: Option<StyleSpec>,
+    #[serde(default)]
+    extensions: HashMap<String, StyleSpec>,
+}
+
+/// A style in a theme file, either just a color like `"red"` or a table
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StyleSpec {
+    Color(String),
+    Table {
+        color: Option<String>,
+        #[serde(default)]
+        bold: bool,
+        #[serde(default)]
+        dimmed: bool,
+        #[serde(default)]
+        italic: bool,
+        #[serde(default)]
+        underline: bool,
+    },
+}
+
+impl StyleSpec {
+    fn style(self) -> anyhow::Result<Style> {
+        let color = |name: &str| {
+            name.parse::<Color>()
+                .map_err(|()| anyhow::anyhow!("Unknown color {name}"))
+        };
+        match self {
+            Self::Color(name) => Ok(Style::color(color(&name)?)),
+            Self::Table {
+                color: name,
+                bold,
+                dimmed,
+                italic,
+                underline,
+            } => Ok(Style {
+                color: name.as_deref().map(color).transpose()?,
+                bold,
+                dimmed,
+                italic,
+                underline,
+            }),
+        }
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::record::Record;
+
+/// A node of the `--format tree-json` output. Directories always have a
+/// `children` array, even if it's empty
+#[derive(Serialize)]
+pub struct Node {
+    #[serde(flatten)]
+    value: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    children: Option<Vec<Node>>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Value {
+    Entry {
+        #[serde(flatten)]
+        record: Record,
+        /// Set on directories whose contents couldn't be read
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error: Option<String>,
+    },
+    /// An entry we couldn't read, kept as a leaf so the rest of the tree survives
+    Error {
+        path: String,
+        name: String,
+        kind: &'static str,
+        error: String,
+    },
+}
+
+/// Collects the listing, nesting every entry under its parent directory
+#[derive(Default)]
+pub struct TreeBuilder {
+    nodes: Vec<(PathBuf, Value)>,
+    indices: HashMap<PathBuf, usize>,
+}
+
+impl TreeBuilder {
+    pub fn add_entry(&mut self, path: PathBuf, record: Record) {
+        self.indices.insert(path.clone(), self.nodes.len());
+        self.nodes.push((
+            path,
+            Value::Entry {
+                record,
+                error: None,
+            },
+        ));
+    }
+
+    pub fn add_error(&mut self, path: PathBuf, error: String) {
+        // Directories we can't read the contents of have already been added
+        if let Some(&index) = self.indices.get(&path) {
+            if let (
+                _,
+                Value::Entry {
+                    error: existing, ..
+                },
+            ) = &mut self.nodes[index]
+            {
+                *existing = Some(error);
+                return;
+            }
+        }
+
+        self.indices.insert(path.clone(), self.nodes.len());
+        let value = Value::Error {
+            path: path.to_string_lossy().into_owned(),
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            kind: "error",
+            error,
+        };
+        self.nodes.push((path, value));
+    }
+
+    /// Assembles the tree below `root`. Entries whose parent wasn't listed,
+    /// e.g. because of `--min-depth`, are placed directly below the root
+    pub fn finish(self, root_path: &Path, root: Record) -> Node {
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        let mut top_level = Vec::new();
+
+        // The root itself is listed as well with `--min-depth 0`
+        let listed_root = self.indices.get(root_path).copied();
+
+        for (index, (path, _)) in self.nodes.iter().enumerate() {
+            if Some(index) == listed_root {
+                continue;
+            }
+            match path.parent().and_then(|parent| self.indices.get(parent)) {
+                Some(&parent) if Some(parent) != listed_root => children[parent].push(index),
+                _ => top_level.push(index),
+            }
+        }
+
+        let mut values: Vec<Option<Value>> = self
+            .nodes
+            .into_iter()
+            .map(|(_, value)| Some(value))
+            .collect();
+
+        let root_value = match listed_root {
+            // SAFETY: Nodes are only taken once, and the root isn't anyone's child
+            Some(index) => values[index].take().unwrap(),
+            None => Value::Entry {
+                record: root,
+                error: None,
+            },
+        };
+
+        Node {
+            value: root_value,
+            children: Some(
+                top_level
+                    .into_iter()
+                    .map(|index| build(index, &mut values, &children))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn build(index: usize, values: &mut [Option<Value>], children: &[Vec<usize>]) -> Node {
+    // SAFETY: Every node has at most one parent, so it's only taken once
+    let value = values[index].take().unwrap();
+    let is_dir = matches!(&value, Value::Entry { record, .. } if record.kind == "dir");
+
+    Node {
+        value,
+        children: is_dir.then(|| {
+            children[index]
+                .iter()
+                .map(|&child| build(child, values, children))
+                .collect()
+        }),
+    }
+}
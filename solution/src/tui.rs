@@ -0,0 +1,337 @@
+use std::path::{Path, PathBuf};
+
+use crate::{
+    entry::Entry,
+    explorer::Explorer,
+    format::{format_date, DirSize, FormatSize, SIZE_WIDTH},
+    theme::Theme,
+};
+use anyhow::Context;
+use fuzzy_matcher::{skim::SkimMatcherV2, FuzzyMatcher};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::Constraint,
+    style::{Color, Modifier, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, Cell, Row, Table, TableState},
+    DefaultTerminal, Frame,
+};
+
+/// Interactive browser started by `--tui`, showing one directory at a time
+struct App<'theme> {
+    directory: PathBuf,
+    entries: Vec<Entry>,
+    state: TableState,
+    hidden: bool,
+    /// Shown below the table, e.g. when a directory couldn't be read
+    message: Option<String>,
+    /// Fuzzy filter narrowing down the entries shown, opened with `/`
+    filter: Option<Filter>,
+    /// The entries shown, i.e. those matching the filter
+    visible: Vec<Match>,
+    /// How to show the sizes of directories themselves
+    dir_size: DirSize,
+    theme: &'theme Theme,
+}
+
+struct Filter {
+    query: String,
+    /// Whether keys currently go to the query, until Enter is pressed
+    typing: bool,
+}
+
+/// An entry matching the filter
+struct Match {
+    index: usize,
+    /// Indices of the characters in the name matching the query
+    highlighted: Vec<usize>,
+}
+
+impl<'theme> App<'theme> {
+    fn new(directory: PathBuf, hidden: bool, dir_size: DirSize, theme: &'theme Theme) -> Self {
+        let mut app = Self {
+            directory,
+            entries: Vec::new(),
+            state: TableState::default(),
+            hidden,
+            message: None,
+            filter: None,
+            visible: Vec::new(),
+            dir_size,
+            theme,
+        };
+        app.load();
+        app
+    }
+
+    /// Lists the current directory, selecting the first entry
+    fn load(&mut self) {
+        self.message = None;
+        self.filter = None;
+        self.entries.clear();
+
+        for entry in Explorer::new(&self.directory)
+            .max_depth(1)
+            .hidden(self.hidden)
+            .sort_by_file_name(true)
+            .walk()
+        {
+            match entry {
+                Ok(entry) => self.entries.push(entry),
+                Err(error) => self.message = Some(format!("{error:#}")),
+            }
+        }
+
+        self.apply_filter();
+    }
+
+    /// Narrows the visible entries down to those matching the filter,
+    /// selecting the first of them
+    fn apply_filter(&mut self) {
+        let query = self.filter.as_ref().map_or("", |filter| &filter.query);
+        let matcher = SkimMatcherV2::default();
+
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter_map(|(index, entry)| {
+                if query.is_empty() {
+                    return Some(Match {
+                        index,
+                        highlighted: Vec::new(),
+                    });
+                }
+                let (_, highlighted) = matcher.fuzzy_indices(&entry.name, query)?;
+                Some(Match { index, highlighted })
+            })
+            .collect();
+
+        self.state.select((!self.visible.is_empty()).then_some(0));
+    }
+
+    fn selected(&self) -> Option<&Entry> {
+        let selected = self.visible.get(self.state.selected()?)?;
+        self.entries.get(selected.index)
+    }
+
+    fn enter_selected(&mut self) {
+        let Some(entry) = self.selected() else {
+            return;
+        };
+
+        if entry.metadata.is_dir() || entry.path.is_dir() {
+            self.directory = entry.path.clone();
+            self.load();
+        }
+    }
+
+    /// Goes to the parent directory, selecting the directory we came from
+    fn leave(&mut self) {
+        let Some(parent) = self.directory.parent().map(Path::to_path_buf) else {
+            return;
+        };
+
+        let previous = std::mem::replace(&mut self.directory, parent);
+        self.load();
+
+        if let Some(index) = self.entries.iter().position(|entry| entry.path == previous) {
+            self.state.select(Some(index));
+        }
+    }
+
+    /// Handles a key press while typing a query
+    fn type_query(&mut self, key: KeyCode) {
+        let Some(filter) = &mut self.filter else {
+            return;
+        };
+
+        match key {
+            KeyCode::Esc => self.filter = None,
+            KeyCode::Enter => filter.typing = false,
+            KeyCode::Backspace => {
+                filter.query.pop();
+            }
+            KeyCode::Char(c) => filter.query.push(c),
+            KeyCode::Up => return self.state.select_previous(),
+            KeyCode::Down => return self.state.select_next(),
+            _ => return,
+        }
+
+        self.apply_filter();
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let rows = self.visible.iter().map(|visible| {
+            let entry = &self.entries[visible.index];
+            let modified = entry
+                .metadata
+                .modified()
+                .map(format_date)
+                .unwrap_or_default();
+
+            let style = tui_style(if entry.metadata.is_file() {
+                self.theme.file_style(&entry.name)
+            } else if entry.metadata.is_dir() {
+                self.theme.dir
+            } else {
+                self.theme.symlink
+            });
+            let highlighted_style = style.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+            let name: Line = entry
+                .name
+                .chars()
+                .enumerate()
+                .map(|(index, c)| {
+                    if visible.highlighted.contains(&index) {
+                        Span::styled(c.to_string(), highlighted_style)
+                    } else {
+                        Span::styled(c.to_string(), style)
+                    }
+                })
+                .collect();
+
+            let size = FormatSize {
+                size: entry.size,
+                directory: entry.metadata.is_dir().then_some(self.dir_size),
+                width: SIZE_WIDTH,
+                theme: self.theme,
+            };
+            Row::new([
+                Cell::from(size.text()).style(tui_style(size.style())),
+                Cell::from(modified).style(tui_style(self.theme.modified)),
+                Cell::from(name),
+            ])
+        });
+
+        let header = Row::new([
+            format!("{:>SIZE_WIDTH$}", "Size"),
+            "Modified at".into(),
+            "Name".into(),
+        ])
+        .style(tui_style(self.theme.header));
+
+        let mut block = Block::bordered().title(format!(" {} ", self.directory.display()));
+        if let Some(filter) = &self.filter {
+            let cursor = if filter.typing { "█" } else { "" };
+            block = block.title_bottom(format!(" /{}{cursor} ", filter.query).bold());
+        } else if let Some(message) = &self.message {
+            block = block.title_bottom(
+                Span::from(format!(" {message} ")).style(tui_style(self.theme.anomaly)),
+            );
+        } else {
+            block = block.title_bottom(" ↑↓ move · Enter open · Backspace up · / search · q quit ");
+        }
+
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Length(SIZE_WIDTH as u16),
+                Constraint::Length(25),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(header)
+        .block(block)
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol("> ");
+
+        frame.render_stateful_widget(table, frame.area(), &mut self.state);
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> anyhow::Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+
+            if self.filter.as_ref().is_some_and(|filter| filter.typing) {
+                self.type_query(key.code);
+                continue;
+            }
+
+            match key.code {
+                KeyCode::Char('/') => {
+                    self.filter = Some(Filter {
+                        query: String::new(),
+                        typing: true,
+                    });
+                }
+                KeyCode::Esc if self.filter.is_some() => {
+                    self.filter = None;
+                    self.apply_filter();
+                }
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.state.select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.state.select_next(),
+                KeyCode::PageUp => self.state.scroll_up_by(10),
+                KeyCode::PageDown => self.state.scroll_down_by(10),
+                KeyCode::Home => self.state.select_first(),
+                KeyCode::End => self.state.select_last(),
+                KeyCode::Enter | KeyCode::Right => self.enter_selected(),
+                KeyCode::Backspace | KeyCode::Left => self.leave(),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// The TUI's equivalent of a listing's `style`. Colors are left out when
+/// they're turned off, e.g. with `--color never`
+fn tui_style(style: crate::theme::Style) -> Style {
+    let mut tui_style = Style::new();
+    if let Some(color) = style
+        .color
+        .filter(|_| colored::control::SHOULD_COLORIZE.should_colorize())
+    {
+        tui_style = tui_style.fg(match color {
+            colored::Color::Black => Color::Black,
+            colored::Color::Red => Color::Red,
+            colored::Color::Green => Color::Green,
+            colored::Color::Yellow => Color::Yellow,
+            colored::Color::Blue => Color::Blue,
+            colored::Color::Magenta => Color::Magenta,
+            colored::Color::Cyan => Color::Cyan,
+            colored::Color::White => Color::Gray,
+            colored::Color::BrightBlack => Color::DarkGray,
+            colored::Color::BrightRed => Color::LightRed,
+            colored::Color::BrightGreen => Color::LightGreen,
+            colored::Color::BrightYellow => Color::LightYellow,
+            colored::Color::BrightBlue => Color::LightBlue,
+            colored::Color::BrightMagenta => Color::LightMagenta,
+            colored::Color::BrightCyan => Color::LightCyan,
+            colored::Color::BrightWhite => Color::White,
+            colored::Color::TrueColor { r, g, b } => Color::Rgb(r, g, b),
+        });
+    }
+    for (enabled, modifier) in [
+        (style.bold, Modifier::BOLD),
+        (style.dimmed, Modifier::DIM),
+        (style.italic, Modifier::ITALIC),
+        (style.underline, Modifier::UNDERLINED),
+    ] {
+        if enabled {
+            tui_style = tui_style.add_modifier(modifier);
+        }
+    }
+    tui_style
+}
+
+/// Browses `directory` interactively with the colors of `theme` until the
+/// user quits, showing the sizes of directories themselves like `dir_size`
+pub fn run(directory: &Path, hidden: bool, dir_size: DirSize, theme: &Theme) -> anyhow::Result<()> {
+    // Resolve the directory so we can go above where we started
+    let directory = directory
+        .canonicalize()
+        .context(format!("Failed resolving {}", directory.display()))?;
+
+    let mut terminal = ratatui::init();
+    let result = App::new(directory, hidden, dir_size, theme).run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,61 @@
+use std::{
+    io::{ErrorKind, Write},
+    path::Path,
+};
+
+use anyhow::Context;
+
+use crate::{
+    hash::{hash_file, HashAlgorithm},
+    theme::Theme,
+};
+
+/// Checks every `<hash>  <path>` line of a `sha256sum`-style file, with
+/// paths relative to `root`, printing OK, FAILED or MISSING for each, styled
+/// by `theme`.
+///
+/// Returns whether every file matched its hash.
+pub fn verify(
+    sums_file: &Path,
+    root: &Path,
+    algorithm: HashAlgorithm,
+    mut output: impl Write,
+    theme: &Theme,
+) -> anyhow::Result<bool> {
+    let sums = std::fs::read_to_string(sums_file)
+        .context(format!("Failed reading {}", sums_file.display()))?;
+
+    let mut all_ok = true;
+
+    for (index, line) in sums.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // `sha256sum` separates with two spaces, or ` *` for files hashed in binary mode
+        let (expected, path) = line
+            .split_once(' ')
+            .and_then(|(hash, rest)| Some((hash, rest.strip_prefix([' ', '*'])?)))
+            .context(format!(
+                "Line {} of {} isn't formatted like `<hash>  <path>`",
+                index + 1,
+                sums_file.display()
+            ))?;
+
+        let (ok, status) = match hash_file(&root.join(path), algorithm) {
+            Ok(actual) if actual.eq_ignore_ascii_case(expected) => (true, theme.ok.paint("OK")),
+            Ok(_) => (false, theme.failed.paint("FAILED")),
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                (false, theme.missing.paint("MISSING"))
+            }
+            Err(error) => {
+                return Err(error).context(format!("Failed hashing {path}"));
+            }
+        };
+
+        all_ok &= ok;
+        writeln!(output, "{path}: {status}")?;
+    }
+
+    Ok(all_ok)
+}
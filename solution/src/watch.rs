@@ -0,0 +1,106 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc,
+};
+
+use anyhow::Context;
+use notify::{
+    event::{ModifyKind, RenameMode},
+    EventKind, RecursiveMode, Watcher,
+};
+
+use crate::{
+    filter::{NameFilter, PathFilter},
+    is_hidden_entry,
+    theme::Theme,
+};
+
+/// Prints the changes below a directory as they happen, for `--watch`, with
+/// the same filters as the listing
+pub struct Watch<'filters> {
+    /// The directory as given, which the paths printed start with
+    pub root: &'filters Path,
+    /// The directory resolved, which the watched paths start with
+    pub absolute_root: &'filters Path,
+    pub min_depth: usize,
+    pub max_depth: usize,
+    pub hidden: bool,
+    pub path_filter: &'filters PathFilter,
+    pub name_filter: &'filters NameFilter,
+    /// Styles the `+`, `~` and `-` markers
+    pub theme: &'filters Theme,
+}
+
+impl Watch<'_> {
+    /// Writes a line for every entry created (`+`), modified (`~`) or removed
+    /// (`-`) until the watcher stops or writing fails
+    pub fn run(&self, mut output: impl Write) -> anyhow::Result<()> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher =
+            notify::recommended_watcher(sender).context("Failed starting to watch")?;
+        watcher
+            .watch(self.absolute_root, RecursiveMode::Recursive)
+            .context(format!("Failed watching {}", self.root.display()))?;
+
+        for event in events {
+            let event = event.context("Failed watching for changes")?;
+            let marker = match event.kind {
+                EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                    self.theme.added.paint("+")
+                }
+                EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                    self.theme.removed.paint("-")
+                }
+                // Renames are also reported as separate `From` and `To` events
+                EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => continue,
+                EventKind::Modify(_) => self.theme.changed.paint("~"),
+                _ => continue,
+            };
+
+            for path in &event.paths {
+                if let Some(relative) = self.shown_path(path) {
+                    writeln!(output, "{marker} {}", self.root.join(relative).display())?;
+                }
+            }
+            output.flush()?;
+        }
+        Ok(())
+    }
+
+    /// The path of the entry at the watched `path` relative to the root, if
+    /// it passes the filters
+    fn shown_path(&self, path: &Path) -> Option<PathBuf> {
+        let relative = path.strip_prefix(self.absolute_root).ok()?;
+        let depth = relative.components().count();
+        if depth < self.min_depth || depth > self.max_depth {
+            return None;
+        }
+
+        let is_dir = path
+            .symlink_metadata()
+            .is_ok_and(|metadata| metadata.is_dir());
+        // The listing skips what's within hidden or excluded directories too
+        let is_hidden = |ancestor: &Path| {
+            ancestor.file_name().is_some_and(|name| {
+                is_hidden_entry(name, || self.absolute_root.join(ancestor).metadata().ok())
+            })
+        };
+        for ancestor in relative
+            .ancestors()
+            .filter(|ancestor| *ancestor != Path::new(""))
+        {
+            if (!self.hidden && is_hidden(ancestor))
+                || self
+                    .path_filter
+                    .is_excluded(ancestor, ancestor != relative || is_dir)
+            {
+                return None;
+            }
+        }
+
+        let name = relative.file_name()?.to_string_lossy();
+        (self.path_filter.is_included(relative) && self.name_filter.matches(&name, is_dir))
+            .then(|| relative.to_path_buf())
+    }
+}
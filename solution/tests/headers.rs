@@ -0,0 +1,41 @@
+use std::{fs, path::Path, process::Command};
+
+/// What `explore` prints for `root` with `args`
+fn explore(root: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_explore"))
+        .arg("--path")
+        .arg(root)
+        .args(["--color", "never", "--no-pager"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert!(output.status.success(), "{output:?}");
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn no_headers_when_nothing_matches() {
+    let root = tempfile::tempdir().unwrap();
+    fs::write(root.path().join("file.txt"), "").unwrap();
+
+    let listing = explore(
+        root.path(),
+        &["--headers", "--no-headers-when-empty", "--glob", "*.zzz"],
+    );
+    assert_eq!(listing, "");
+}
+
+#[test]
+fn headers_before_the_first_match() {
+    let root = tempfile::tempdir().unwrap();
+    fs::write(root.path().join("file.txt"), "").unwrap();
+
+    let listing = explore(
+        root.path(),
+        &["--headers", "--no-headers-when-empty", "--glob", "*.txt"],
+    );
+    let lines: Vec<_> = listing.lines().collect();
+    assert_eq!(lines.len(), 2, "{listing}");
+    assert!(lines[0].contains("Size") && lines[0].ends_with("Name"));
+    assert!(lines[1].ends_with("file.txt"));
+}